@@ -1,13 +1,55 @@
-use super::OutputFormat;
-use super::converting::ConversionResult;
+use super::converting::{self, ConversionResult};
 use serde::Serialize;
+use std::collections::BTreeMap;
+
+// Alfred's own stop sign, shown on items explaining why an input could not be converted.
+const ERROR_ICON: &str =
+    "/System/Library/CoreServices/CoreTypes.bundle/Contents/Resources/AlertStopIcon.icns";
 
 #[derive(Serialize)]
 struct Item {
-    uid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uid: Option<String>,
     title: String,
     subtitle: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arg: Option<String>,
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    autocomplete: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<Text>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    variables: BTreeMap<&'static str, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mods: Option<Mods>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icon: Option<Icon>,
+}
+
+/// What ⌘C copies and ⌘L shows in large type.
+#[derive(Serialize)]
+struct Text {
+    copy: String,
+    largetype: String,
+}
+
+/// The alternative actions while a modifier key is held.
+#[derive(Serialize)]
+struct Mods {
+    alt: Mod,
+    cmd: Mod,
+}
+
+#[derive(Serialize)]
+struct Mod {
     arg: String,
+    subtitle: String,
+}
+
+#[derive(Serialize)]
+struct Icon {
+    path: &'static str,
 }
 
 #[derive(Serialize)]
@@ -15,36 +57,170 @@ struct Alfred {
     items: Vec<Item>,
 }
 
+/// One item per conversion, whose `uid` is its key with a repeated key suffixed like in records, so
+/// Alfred learns each output's position separately.
 pub fn output_json(conversion_results: &[ConversionResult]) -> String {
-    let items: Vec<_> = conversion_results
+    let items: Vec<_> = converting::unique_keys(conversion_results)
+        .into_iter()
+        .zip(conversion_results)
+        .map(|(uid, conversion_result)| item(uid, conversion_result))
+        .collect();
+
+    serde_json::to_string(&Alfred { items }).unwrap()
+}
+
+/// Flattens several rows of conversions into one list, suffixing each `uid` with its row index so
+/// they stay unique.
+pub fn output_json_rows(rows: &[Vec<ConversionResult>]) -> String {
+    let items: Vec<_> = rows
         .iter()
-        .map(|conversion_result| Item {
-            uid: match &conversion_result.format {
-                OutputFormat::Utc => String::from("utc"),
-                OutputFormat::Zoned => String::from("zoned"),
-                OutputFormat::Seconds => String::from("seconds"),
-                OutputFormat::Millis => String::from("millis"),
-                OutputFormat::Nanos => String::from("nanos"),
-                OutputFormat::Duration => String::from("duration"),
-                OutputFormat::DurationSinceUnits(duration_unit) => {
-                    format!("duration_since_{duration_unit:?}").to_lowercase()
-                }
-            },
-            title: conversion_result.converted_text.clone(),
-            subtitle: match &conversion_result.format {
-                OutputFormat::Utc => String::from("RFC3339 - UTC"),
-                OutputFormat::Zoned => String::from("RFC3339 - Zoned"),
-                OutputFormat::Seconds => String::from("Epoch Seconds"),
-                OutputFormat::Millis => String::from("Epoch Millis"),
-                OutputFormat::Nanos => String::from("Epoch Nanoseconds"),
-                OutputFormat::Duration => String::from("Duration"),
-                OutputFormat::DurationSinceUnits(duration_unit) => {
-                    format!("Duration {duration_unit:?}")
-                }
-            },
-            arg: conversion_result.converted_text.clone(),
+        .enumerate()
+        .flat_map(|(index, row)| {
+            converting::unique_keys(row).into_iter().zip(row).map(
+                move |(uid, conversion_result)| item(format!("{uid}_{index}"), conversion_result),
+            )
         })
         .collect();
 
     serde_json::to_string(&Alfred { items }).unwrap()
 }
+
+/// A single item that cannot be actioned, explaining why `input` could not be converted.
+pub fn output_error(error: &str, input: &str) -> String {
+    let item = Item {
+        uid: None,
+        title: error.to_string(),
+        subtitle: input.to_string(),
+        arg: None,
+        valid: false,
+        autocomplete: None,
+        text: None,
+        variables: BTreeMap::new(),
+        mods: None,
+        icon: Some(Icon { path: ERROR_ICON }),
+    };
+
+    serde_json::to_string(&Alfred { items: vec![item] }).unwrap()
+}
+
+fn item(uid: String, conversion_result: &ConversionResult) -> Item {
+    let key = conversion_result.format.key();
+    let label = conversion_result.format.label();
+    let value = &conversion_result.converted_text;
+    Item {
+        uid: Some(uid),
+        title: value.clone(),
+        subtitle: label.clone(),
+        arg: Some(value.clone()),
+        valid: true,
+        autocomplete: Some(value.clone()),
+        text: Some(Text {
+            copy: value.clone(),
+            largetype: value.clone(),
+        }),
+        variables: BTreeMap::from([("output", key), ("label", label.clone())]),
+        mods: Some(Mods {
+            alt: Mod {
+                arg: format!("{label}: {value}"),
+                subtitle: String::from("Copy with its label"),
+            },
+            cmd: Mod {
+                arg: sql_literal(value),
+                subtitle: String::from("Paste as a SQL literal"),
+            },
+        }),
+        icon: None,
+    }
+}
+
+// Numbers are pasted as they are, anything else as a string with its quotes doubled.
+fn sql_literal(value: &str) -> String {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    let numeric = digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.matches('.').count() <= 1;
+    if numeric {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OutputFormat;
+
+    #[test]
+    fn items_with_modifiers() {
+        let results = [ConversionResult {
+            converted_text: String::from("1575149020.890"),
+            format: OutputFormat::Epoch(crate::EpochUnit::Seconds, 3),
+        }];
+        assert_eq!(
+            output_json(&results),
+            concat!(
+                r#"{"items":[{"uid":"epoch_seconds_3","title":"1575149020.890","#,
+                r#""subtitle":"Epoch Seconds (3 decimals)","arg":"1575149020.890","valid":true,"#,
+                r#""autocomplete":"1575149020.890","#,
+                r#""text":{"copy":"1575149020.890","largetype":"1575149020.890"},"#,
+                r#""variables":{"label":"Epoch Seconds (3 decimals)","output":"epoch_seconds_3"},"#,
+                r#""mods":{"alt":{"arg":"Epoch Seconds (3 decimals): 1575149020.890","#,
+                r#""subtitle":"Copy with its label"},"#,
+                r#""cmd":{"arg":"1575149020.890","subtitle":"Paste as a SQL literal"}}}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn repeated_keys_get_unique_uids() {
+        let results = || {
+            vec![
+                ConversionResult {
+                    converted_text: String::from("30/11"),
+                    format: OutputFormat::Strftime(String::from("%d/%m")),
+                },
+                ConversionResult {
+                    converted_text: String::from("2019"),
+                    format: OutputFormat::Strftime(String::from("%Y")),
+                },
+            ]
+        };
+        let uids = |json: &str| -> Vec<String> {
+            let value: serde_json::Value = serde_json::from_str(json).unwrap();
+            value["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item["uid"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(uids(&output_json(&results())), ["strftime", "strftime_2"]);
+        assert_eq!(
+            uids(&output_json_rows(&[results(), results()])),
+            ["strftime_0", "strftime_2_0", "strftime_1", "strftime_2_1"]
+        );
+    }
+
+    #[test]
+    fn sql_literals() {
+        assert_eq!(sql_literal("1575149020890"), "1575149020890");
+        assert_eq!(sql_literal("-12.5"), "-12.5");
+        assert_eq!(
+            sql_literal("2019-11-30T21:23:40.890Z"),
+            "'2019-11-30T21:23:40.890Z'"
+        );
+        assert_eq!(sql_literal("it's"), "'it''s'");
+        assert_eq!(sql_literal("1.2.3"), "'1.2.3'");
+    }
+
+    #[test]
+    fn errors_are_not_actionable() {
+        assert_eq!(
+            output_error("Input format not recognized", "nonsense"),
+            format!(
+                r#"{{"items":[{{"title":"Input format not recognized","subtitle":"nonsense","valid":false,"icon":{{"path":"{ERROR_ICON}"}}}}]}}"#
+            )
+        );
+    }
+}
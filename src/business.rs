@@ -0,0 +1,266 @@
+use crate::{BusinessCalendar, TimeZoneSpec, snapping};
+use chrono::Days;
+use chrono::prelude::*;
+use std::collections::BTreeSet;
+
+/// Moves `instant` by `days` business days in the calendar of `timezone` (UTC when `None`),
+/// keeping the wall-clock time. Each step lands on the next (or previous) day that is neither a
+/// weekend day nor a holiday, so starting on a Saturday, `+1` is the following Monday.
+pub fn add_business_days(
+    instant: &DateTime<Utc>,
+    days: i64,
+    calendar: &BusinessCalendar,
+    timezone: Option<TimeZoneSpec>,
+) -> Option<DateTime<Utc>> {
+    let every_day_is_weekend = (0..7)
+        .filter_map(|n| Weekday::try_from(n).ok())
+        .all(|day| calendar.weekend.contains(&day));
+    if days != 0 && every_day_is_weekend {
+        return None;
+    }
+    let local = match timezone {
+        Some(tz) => tz.utc_to_naive(instant),
+        None => instant.naive_utc(),
+    };
+    let mut date = local.date();
+    for _ in 0..days.unsigned_abs() {
+        date = next_business_day(date, days.is_negative(), calendar)?;
+    }
+    snapping::to_utc(date.and_time(local.time()), timezone)
+}
+
+fn next_business_day(
+    mut date: NaiveDate,
+    backwards: bool,
+    calendar: &BusinessCalendar,
+) -> Option<NaiveDate> {
+    loop {
+        date = if backwards {
+            date.checked_sub_days(Days::new(1))?
+        } else {
+            date.checked_add_days(Days::new(1))?
+        };
+        if calendar.is_business_day(date) {
+            return Some(date);
+        }
+    }
+}
+
+/// Counts the business days after `start` up to and including `end`, negated when `end` is
+/// before `start`. Adding the result to `start` with `add_business_days` lands on `end` whenever
+/// `end` is itself a business day.
+pub fn business_days_between(start: NaiveDate, end: NaiveDate, calendar: &BusinessCalendar) -> i64 {
+    let (from, to, sign) = if end < start {
+        (end, start, -1)
+    } else {
+        (start, end, 1)
+    };
+    let count = from
+        .iter_days()
+        .skip(1)
+        .take_while(|date| *date <= to)
+        .filter(|date| calendar.is_business_day(*date))
+        .count();
+    sign * i64::try_from(count).unwrap_or(i64::MAX)
+}
+
+/// Reads holiday dates from an iCalendar file (every `VEVENT`, with all-day events spanning up to
+/// their exclusive `DTEND`) or from plain text with one `YYYY-MM-DD` date per line. In plain text,
+/// anything after the date and everything after a `#` is ignored.
+pub fn parse_holidays(text: &str) -> Result<BTreeSet<NaiveDate>, String> {
+    if text.trim_start().starts_with("BEGIN:VCALENDAR") {
+        parse_icalendar(text)
+    } else {
+        parse_plain_dates(text)
+    }
+}
+
+fn parse_plain_dates(text: &str) -> Result<BTreeSet<NaiveDate>, String> {
+    let mut holidays = BTreeSet::new();
+    for (index, line) in text.lines().enumerate() {
+        let content = line.split('#').next().unwrap_or_default();
+        let Some(first) = content.split_whitespace().next() else {
+            continue;
+        };
+        let date = NaiveDate::parse_from_str(first, "%Y-%m-%d").map_err(|_| {
+            format!(
+                "Invalid date on line {} of holidays file: {line}",
+                index + 1
+            )
+        })?;
+        holidays.insert(date);
+    }
+    Ok(holidays)
+}
+
+fn parse_icalendar(text: &str) -> Result<BTreeSet<NaiveDate>, String> {
+    // Long lines are folded onto continuation lines that start with a space or tab
+    let unfolded = text
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+    let mut holidays = BTreeSet::new();
+    let mut start = None;
+    let mut end = None;
+    for line in unfolded.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let property = name.split(';').next().unwrap_or_default();
+        match property.to_ascii_uppercase().as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VEVENT") => (start, end) = (None, None),
+            "DTSTART" => start = Some(parse_icalendar_date(line, value)?),
+            // A timed event only blocks the day it starts on
+            "DTEND" if !value.contains('T') => end = Some(parse_icalendar_date(line, value)?),
+            "END" if value.eq_ignore_ascii_case("VEVENT") => {
+                let Some(first) = start else {
+                    return Err(String::from("Holidays file has an event without DTSTART"));
+                };
+                holidays.insert(first);
+                if let Some(last) = end {
+                    holidays.extend(first.iter_days().take_while(|date| *date < last));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(holidays)
+}
+
+fn parse_icalendar_date(line: &str, value: &str) -> Result<NaiveDate, String> {
+    value
+        .get(..8)
+        .and_then(|digits| NaiveDate::parse_from_str(digits, "%Y%m%d").ok())
+        .ok_or_else(|| format!("Invalid date in holidays file: {line}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn datetime(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().to_utc()
+    }
+
+    fn calendar_with_holidays(holidays: &[&str]) -> BusinessCalendar {
+        BusinessCalendar {
+            holidays: holidays.iter().map(|s| date(s)).collect(),
+            ..BusinessCalendar::default()
+        }
+    }
+
+    #[test]
+    fn add_skips_weekends_and_holidays() {
+        // 2020-12-24 is a Thursday
+        let calendar = calendar_with_holidays(&["2020-12-25"]);
+        assert_eq!(
+            add_business_days(&datetime("2020-12-24T09:30:00Z"), 1, &calendar, None),
+            Some(datetime("2020-12-28T09:30:00Z"))
+        );
+        assert_eq!(
+            add_business_days(&datetime("2020-12-28T09:30:00Z"), -2, &calendar, None),
+            Some(datetime("2020-12-23T09:30:00Z"))
+        );
+        assert_eq!(
+            add_business_days(&datetime("2020-12-26T09:30:00Z"), 0, &calendar, None),
+            Some(datetime("2020-12-26T09:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn add_with_custom_weekend() {
+        // Friday and Saturday weekend; 2020-12-24 is a Thursday
+        let calendar = BusinessCalendar {
+            weekend: vec![Weekday::Fri, Weekday::Sat],
+            ..BusinessCalendar::default()
+        };
+        assert_eq!(
+            add_business_days(&datetime("2020-12-24T09:30:00Z"), 1, &calendar, None),
+            Some(datetime("2020-12-27T09:30:00Z"))
+        );
+        let no_business_days = BusinessCalendar {
+            weekend: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+                Weekday::Sat,
+                Weekday::Sun,
+            ],
+            ..BusinessCalendar::default()
+        };
+        assert_eq!(
+            add_business_days(
+                &datetime("2020-12-24T09:30:00Z"),
+                1,
+                &no_business_days,
+                None
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn count_between_dates() {
+        let calendar = calendar_with_holidays(&["2020-12-25"]);
+        assert_eq!(
+            business_days_between(date("2020-12-24"), date("2020-12-31"), &calendar),
+            4
+        );
+        assert_eq!(
+            business_days_between(date("2020-12-31"), date("2020-12-24"), &calendar),
+            -4
+        );
+        assert_eq!(
+            business_days_between(date("2020-12-26"), date("2020-12-27"), &calendar),
+            0
+        );
+    }
+
+    #[test]
+    fn plain_text_holidays() {
+        let holidays =
+            parse_holidays("# 2020 holidays\n2020-12-25 Christmas Day\n\n2021-01-01\n").unwrap();
+        assert_eq!(
+            holidays,
+            BTreeSet::from([date("2020-12-25"), date("2021-01-01")])
+        );
+        assert_eq!(
+            parse_holidays("2020-12-25\nChristmas\n"),
+            Err(String::from(
+                "Invalid date on line 2 of holidays file: Christmas"
+            ))
+        );
+    }
+
+    #[test]
+    fn icalendar_holidays() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+            VERSION:2.0\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART;VALUE=DATE:20201224\r\n\
+            DTEND;VALUE=DATE:20201227\r\n\
+            SUMMARY:Christmas\r\n\
+            \x20break\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART:20210101T000000Z\r\n\
+            DTEND:20210101T235959Z\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        assert_eq!(
+            parse_holidays(ics).unwrap(),
+            BTreeSet::from([
+                date("2020-12-24"),
+                date("2020-12-25"),
+                date("2020-12-26"),
+                date("2021-01-01"),
+            ])
+        );
+    }
+}
@@ -1,7 +1,11 @@
-use crate::{DurationUnit, OutputFormat, TimeZoneSpec};
+use crate::business;
+use crate::duration::{self, CalendarDuration};
+use crate::{
+    BusinessCalendar, ConvertOptions, DurationPrecision, DurationUnit, EpochUnit, OutputFormat,
+    Precision, Rounding, TimeZoneSpec,
+};
 use chrono::prelude::*;
-use humantime::format_duration;
-use std::time::Duration;
+use std::iter;
 
 #[derive(PartialEq, Eq, Debug)]
 pub struct ConversionResult {
@@ -13,36 +17,102 @@ pub fn convert(
     parsed_input: &DateTime<Utc>,
     now: &DateTime<Utc>,
     outputs: &[OutputFormat],
-    display_tz: Option<TimeZoneSpec>,
-    extra_duration_unit: Option<DurationUnit>,
+    options: &ConvertOptions,
 ) -> Vec<ConversionResult> {
+    let display_tz = options.display_tz;
+    let seconds_format = options
+        .precision
+        .map_or(SecondsFormat::Millis, Precision::seconds_format);
     let mut results: Vec<ConversionResult> = outputs
         .iter()
         .map(|fmt| {
             let text = match fmt {
-                OutputFormat::Utc => parsed_input.to_rfc3339_opts(SecondsFormat::Millis, true),
+                OutputFormat::Utc => parsed_input.to_rfc3339_opts(seconds_format, true),
                 OutputFormat::Zoned => match display_tz {
-                    Some(tz) => tz.format_rfc3339_millis(parsed_input),
+                    Some(tz) => tz.format_rfc3339(parsed_input, seconds_format),
                     None => parsed_input
                         .with_timezone(&Local)
-                        .to_rfc3339_opts(SecondsFormat::Millis, true),
+                        .to_rfc3339_opts(seconds_format, true),
                 },
-                OutputFormat::Seconds => parsed_input.timestamp().to_string(),
-                OutputFormat::Millis => parsed_input.timestamp_millis().to_string(),
-                OutputFormat::Nanos => parsed_input
-                    .timestamp_nanos_opt()
-                    .map_or_else(|| String::from("out of range"), |n| n.to_string()),
-                OutputFormat::Duration => human_duration_since(parsed_input, now),
-                OutputFormat::DurationSinceUnits(_) => unreachable!(),
+                OutputFormat::Original => parsed_input
+                    .with_timezone(&options.original_offset.unwrap_or(Utc.fix()))
+                    .to_rfc3339_opts(seconds_format, true),
+                OutputFormat::Seconds => {
+                    epoch_output(parsed_input, EpochUnit::Seconds, options.precision)
+                }
+                OutputFormat::Millis => {
+                    epoch_output(parsed_input, EpochUnit::Millis, options.precision)
+                }
+                OutputFormat::Micros => {
+                    epoch_output(parsed_input, EpochUnit::Micros, options.precision)
+                }
+                OutputFormat::Nanos => {
+                    epoch_output(parsed_input, EpochUnit::Nanos, options.precision)
+                }
+                OutputFormat::Epoch(unit, decimals) => format_epoch(parsed_input, *unit, *decimals),
+                OutputFormat::Duration => human_duration_since(
+                    parsed_input,
+                    now,
+                    options.duration_precision,
+                    options.duration_rounding,
+                ),
+                OutputFormat::FuzzyDuration => {
+                    fuzzy_duration_since(parsed_input, now, options.duration_rounding)
+                }
+                OutputFormat::IsoDuration => iso_duration_since(parsed_input, now),
+                OutputFormat::CalendarDuration => {
+                    calendar_duration_since(parsed_input, now, display_tz)
+                }
+                OutputFormat::BusinessDays => {
+                    business_days_since(parsed_input, now, display_tz, &options.business_calendar)
+                }
+                OutputFormat::DayOfWeek => local_date(parsed_input, display_tz)
+                    .format("%A")
+                    .to_string(),
+                OutputFormat::IsoWeek => {
+                    let week = local_date(parsed_input, display_tz).iso_week();
+                    format!("{}-W{:02}", week.year(), week.week())
+                }
+                OutputFormat::Quarter => {
+                    let date = local_date(parsed_input, display_tz);
+                    format!("{}-Q{}", date.year(), date.month0() / 3 + 1)
+                }
+                OutputFormat::FiscalYear => {
+                    let (year, _) = fiscal_year_and_quarter(
+                        local_date(parsed_input, display_tz),
+                        options.fiscal_year_start,
+                    );
+                    format!("FY{year}")
+                }
+                OutputFormat::FiscalQuarter => {
+                    let (year, quarter) = fiscal_year_and_quarter(
+                        local_date(parsed_input, display_tz),
+                        options.fiscal_year_start,
+                    );
+                    format!("FY{year}-Q{quarter}")
+                }
+                OutputFormat::DayOfYear => {
+                    local_date(parsed_input, display_tz).ordinal().to_string()
+                }
+                OutputFormat::Strftime(pattern) => match display_tz {
+                    Some(tz) => tz.format(parsed_input, pattern),
+                    None => parsed_input
+                        .with_timezone(&Local)
+                        .format(pattern)
+                        .to_string(),
+                },
+                OutputFormat::DurationSinceUnits(duration_unit) => {
+                    unit_duration_since(parsed_input, now, *duration_unit)
+                }
             };
             ConversionResult {
                 converted_text: text,
-                format: *fmt,
+                format: fmt.clone(),
             }
         })
         .collect();
 
-    if let Some(duration_unit) = extra_duration_unit {
+    if let Some(duration_unit) = options.extra_duration_unit {
         results.push(ConversionResult {
             converted_text: unit_duration_since(parsed_input, now, duration_unit),
             format: OutputFormat::DurationSinceUnits(duration_unit),
@@ -52,13 +122,108 @@ pub fn convert(
     results
 }
 
-pub fn human_duration_since(input: &DateTime<Utc>, now: &DateTime<Utc>) -> String {
+/// The key of each result, with a `_2`, `_3`... suffix on one that repeats, such as a second
+/// strftime output, so that every key names a single value.
+pub fn unique_keys(conversion_results: &[ConversionResult]) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::with_capacity(conversion_results.len());
+    let mut unique = Vec::with_capacity(conversion_results.len());
+    for conversion_result in conversion_results {
+        let key = conversion_result.format.key();
+        let repeats = keys.iter().filter(|seen| **seen == key).count();
+        unique.push(if repeats == 0 {
+            key.clone()
+        } else {
+            format!("{key}_{}", repeats + 1)
+        });
+        keys.push(key);
+    }
+    unique
+}
+
+// The calendar date of `instant` in `display_tz`, or in the system local zone when `None`.
+fn local_date(instant: &DateTime<Utc>, display_tz: Option<TimeZoneSpec>) -> NaiveDate {
+    match display_tz {
+        Some(tz) => tz.utc_to_naive(instant).date(),
+        None => instant.with_timezone(&Local).date_naive(),
+    }
+}
+
+// A fiscal year is named after the calendar year it ends in, so with an October start,
+// November 2019 is in quarter 1 of FY2020.
+fn fiscal_year_and_quarter(date: NaiveDate, start: Option<Month>) -> (i32, u32) {
+    let start_month0 = start.map_or(0, |month| month.number_from_month() - 1);
+    let months_into_year = (date.month0() + 12 - start_month0) % 12;
+    let year = if start_month0 > 0 && date.month0() >= start_month0 {
+        date.year() + 1
+    } else {
+        date.year()
+    };
+    (year, months_into_year / 3 + 1)
+}
+
+// Number of decimal digits between a whole `unit` and a nanosecond.
+fn nanos_digits(unit: EpochUnit) -> u8 {
+    match unit {
+        EpochUnit::Seconds => 9,
+        EpochUnit::Millis => 6,
+        EpochUnit::Micros => 3,
+        EpochUnit::Nanos => 0,
+    }
+}
+
+// Renders one of the fixed epoch outputs. With a `precision` finer than `unit`, a fractional part
+// is added down to that precision; otherwise the whole (floored) value is shown.
+fn epoch_output(instant: &DateTime<Utc>, unit: EpochUnit, precision: Option<Precision>) -> String {
+    let decimals = match precision {
+        Some(Precision::Secs) | None => 0,
+        Some(Precision::Millis) => nanos_digits(unit).saturating_sub(6),
+        Some(Precision::Micros) => nanos_digits(unit).saturating_sub(3),
+        Some(Precision::Nanos | Precision::Auto) => nanos_digits(unit),
+    };
+    format_epoch(instant, unit, decimals)
+}
+
+// Renders an epoch in `unit` with `decimals` fractional digits, e.g. `1575149020.890` for seconds
+// with 3 decimals. Whole values are floored like chrono's `timestamp*` methods; fractional values
+// are truncated toward zero so pre-1970 instants keep their sign on the fraction (`-0.500`).
+fn format_epoch(instant: &DateTime<Utc>, unit: EpochUnit, decimals: u8) -> String {
+    if decimals == 0 {
+        return match unit {
+            EpochUnit::Seconds => instant.timestamp().to_string(),
+            EpochUnit::Millis => instant.timestamp_millis().to_string(),
+            EpochUnit::Micros => instant.timestamp_micros().to_string(),
+            EpochUnit::Nanos => instant
+                .timestamp_nanos_opt()
+                .map_or_else(|| String::from("out of range"), |n| n.to_string()),
+        };
+    }
+    let unit_digits = usize::from(nanos_digits(unit));
+    let total_nanos = i128::from(instant.timestamp()) * 1_000_000_000
+        + i128::from(instant.timestamp_subsec_nanos());
+    let unit_nanos = 10u128.pow(u32::from(nanos_digits(unit)));
+    let sign = if total_nanos.is_negative() { "-" } else { "" };
+    let whole = total_nanos.unsigned_abs() / unit_nanos;
+    let fraction: String = format!("{:0unit_digits$}", total_nanos.unsigned_abs() % unit_nanos)
+        .chars()
+        .take(unit_digits)
+        .chain(iter::repeat('0'))
+        .take(usize::from(decimals))
+        .collect();
+    format!("{sign}{whole}.{fraction}")
+}
+
+pub fn human_duration_since(
+    input: &DateTime<Utc>,
+    now: &DateTime<Utc>,
+    precision: DurationPrecision,
+    rounding: Rounding,
+) -> String {
     let difference_millis = now.timestamp_millis() - input.timestamp_millis();
 
     let in_future = difference_millis.is_negative();
 
-    let duration = Duration::from_millis(difference_millis.unsigned_abs());
-    let duration_format = format_duration(duration);
+    let duration_format =
+        duration::format_human_millis(difference_millis.unsigned_abs(), precision, rounding);
 
     if in_future {
         format!("in {duration_format}")
@@ -67,6 +232,83 @@ pub fn human_duration_since(input: &DateTime<Utc>, now: &DateTime<Utc>) -> Strin
     }
 }
 
+/// Renders the time since `input` as a single spelled-out unit, like `3 years ago` or
+/// `in 5 minutes`. Differences under a second are `just now`.
+pub fn fuzzy_duration_since(
+    input: &DateTime<Utc>,
+    now: &DateTime<Utc>,
+    rounding: Rounding,
+) -> String {
+    let difference_millis = now.timestamp_millis() - input.timestamp_millis();
+
+    match duration::format_fuzzy_millis(difference_millis.unsigned_abs(), rounding) {
+        None => String::from("just now"),
+        Some(duration_format) if difference_millis.is_negative() => format!("in {duration_format}"),
+        Some(duration_format) => format!("{duration_format} ago"),
+    }
+}
+
+/// Renders the time since `input` as an ISO 8601 duration, negated (`-P...`) when `input` is in
+/// the future. Uses the same fixed-length years and months as `human_duration_since`.
+pub fn iso_duration_since(input: &DateTime<Utc>, now: &DateTime<Utc>) -> String {
+    let difference_millis = now.timestamp_millis() - input.timestamp_millis();
+    CalendarDuration::from_fixed_millis(difference_millis).to_iso_string()
+}
+
+/// Like `human_duration_since`, but counts whole calendar years, months and days between the two
+/// instants' wall-clock times in `display_tz` (system local when `None`) rather than using
+/// fixed-length months and years.
+pub fn calendar_duration_since(
+    input: &DateTime<Utc>,
+    now: &DateTime<Utc>,
+    display_tz: Option<TimeZoneSpec>,
+) -> String {
+    let in_future = input > now;
+    let (start, end) = if in_future {
+        (now, input)
+    } else {
+        (input, now)
+    };
+    let duration = match display_tz {
+        Some(TimeZoneSpec::Named(tz)) => {
+            CalendarDuration::between(&start.with_timezone(&tz), &end.with_timezone(&tz))
+        }
+        Some(TimeZoneSpec::Fixed(offset)) => {
+            CalendarDuration::between(&start.with_timezone(&offset), &end.with_timezone(&offset))
+        }
+        None => CalendarDuration::between(&start.with_timezone(&Local), &end.with_timezone(&Local)),
+    };
+    let duration_format = duration.to_human_string();
+
+    if in_future {
+        format!("in {duration_format}")
+    } else {
+        format!("{duration_format} ago")
+    }
+}
+
+pub fn business_days_since(
+    input: &DateTime<Utc>,
+    now: &DateTime<Utc>,
+    display_tz: Option<TimeZoneSpec>,
+    calendar: &BusinessCalendar,
+) -> String {
+    let days = business::business_days_between(
+        local_date(input, display_tz),
+        local_date(now, display_tz),
+        calendar,
+    );
+    let count = match days.unsigned_abs() {
+        1 => String::from("1 business day"),
+        n => format!("{n} business days"),
+    };
+    match days {
+        0 => count,
+        d if d < 0 => format!("in {count}"),
+        _ => format!("{count} ago"),
+    }
+}
+
 pub fn unit_duration_since(
     input: &DateTime<Utc>,
     now: &DateTime<Utc>,
@@ -126,7 +368,7 @@ mod tests {
     fn missing_input() {
         let now = datetime_from_millis(1572303922748);
         let date = datetime_from_millis(1572213799747);
-        let result = convert(&date, &now, DEFAULT_OUTPUTS, None, None);
+        let result = convert(&date, &now, DEFAULT_OUTPUTS, &ConvertOptions::default());
 
         assert_eq!(
             result,
@@ -157,7 +399,7 @@ mod tests {
     fn epoch_millis_input() {
         let now = datetime_from_millis(1572123676746);
         let date = datetime_from_millis(1572213799747);
-        let result = convert(&date, &now, DEFAULT_OUTPUTS, None, None);
+        let result = convert(&date, &now, DEFAULT_OUTPUTS, &ConvertOptions::default());
 
         assert_eq!(
             result,
@@ -188,7 +430,7 @@ mod tests {
     fn rfc3339_utc() {
         let now = datetime_from_millis(1572213929748);
         let date = datetime_from_millis(1572213799747);
-        let result = convert(&date, &now, DEFAULT_OUTPUTS, None, None);
+        let result = convert(&date, &now, DEFAULT_OUTPUTS, &ConvertOptions::default());
 
         assert_eq!(
             result,
@@ -221,7 +463,7 @@ mod tests {
     fn rfc3339_offset() {
         let now = datetime_from_millis(1572213799749);
         let date = datetime_from_millis(1572213799747);
-        let result = convert(&date, &now, DEFAULT_OUTPUTS, None, None);
+        let result = convert(&date, &now, DEFAULT_OUTPUTS, &ConvertOptions::default());
 
         assert_eq!(
             result,
@@ -256,8 +498,10 @@ mod tests {
             &date,
             &now,
             DEFAULT_OUTPUTS,
-            None,
-            Some(DurationUnit::Milliseconds),
+            &ConvertOptions {
+                extra_duration_unit: Some(DurationUnit::Milliseconds),
+                ..ConvertOptions::default()
+            },
         );
 
         assert_eq!(
@@ -277,8 +521,10 @@ mod tests {
             &date,
             &now,
             DEFAULT_OUTPUTS,
-            None,
-            Some(DurationUnit::Seconds),
+            &ConvertOptions {
+                extra_duration_unit: Some(DurationUnit::Seconds),
+                ..ConvertOptions::default()
+            },
         );
 
         assert_eq!(
@@ -298,8 +544,10 @@ mod tests {
             &date,
             &now,
             DEFAULT_OUTPUTS,
-            None,
-            Some(DurationUnit::Minutes),
+            &ConvertOptions {
+                extra_duration_unit: Some(DurationUnit::Minutes),
+                ..ConvertOptions::default()
+            },
         );
 
         assert_eq!(
@@ -319,8 +567,10 @@ mod tests {
             &date,
             &now,
             DEFAULT_OUTPUTS,
-            None,
-            Some(DurationUnit::Hours),
+            &ConvertOptions {
+                extra_duration_unit: Some(DurationUnit::Hours),
+                ..ConvertOptions::default()
+            },
         );
 
         assert_eq!(
@@ -336,7 +586,15 @@ mod tests {
     fn duration_days() {
         let now = datetime_from_millis(1572123676746);
         let date = datetime_from_millis(1572213799747);
-        let result = convert(&date, &now, DEFAULT_OUTPUTS, None, Some(DurationUnit::Days));
+        let result = convert(
+            &date,
+            &now,
+            DEFAULT_OUTPUTS,
+            &ConvertOptions {
+                extra_duration_unit: Some(DurationUnit::Days),
+                ..ConvertOptions::default()
+            },
+        );
 
         assert_eq!(
             result.last().unwrap(),
@@ -355,8 +613,10 @@ mod tests {
             &date,
             &now,
             DEFAULT_OUTPUTS,
-            None,
-            Some(DurationUnit::Weeks),
+            &ConvertOptions {
+                extra_duration_unit: Some(DurationUnit::Weeks),
+                ..ConvertOptions::default()
+            },
         );
 
         assert_eq!(
@@ -376,8 +636,10 @@ mod tests {
             &date,
             &now,
             DEFAULT_OUTPUTS,
-            None,
-            Some(DurationUnit::Fortnights),
+            &ConvertOptions {
+                extra_duration_unit: Some(DurationUnit::Fortnights),
+                ..ConvertOptions::default()
+            },
         );
 
         assert_eq!(
@@ -397,8 +659,7 @@ mod tests {
             &date,
             &now,
             &[OutputFormat::Seconds, OutputFormat::Millis],
-            None,
-            None,
+            &ConvertOptions::default(),
         );
 
         assert_eq!(
@@ -424,8 +685,10 @@ mod tests {
             &date,
             &now,
             &[OutputFormat::Utc],
-            None,
-            Some(DurationUnit::Days),
+            &ConvertOptions {
+                extra_duration_unit: Some(DurationUnit::Days),
+                ..ConvertOptions::default()
+            },
         );
 
         assert_eq!(result.len(), 2);
@@ -443,7 +706,12 @@ mod tests {
     fn epoch_seconds_output() {
         let now = datetime_from_millis(1572303922748);
         let date = datetime_from_millis(1572213799747);
-        let result = convert(&date, &now, &[OutputFormat::Seconds], None, None);
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::Seconds],
+            &ConvertOptions::default(),
+        );
 
         assert_eq!(result[0].converted_text, "1572213799");
     }
@@ -452,7 +720,12 @@ mod tests {
     fn epoch_nanoseconds_output() {
         let now = datetime_from_millis(1572303922748);
         let date = datetime_from_millis(1572213799747);
-        let result = convert(&date, &now, &[OutputFormat::Nanos], None, None);
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::Nanos],
+            &ConvertOptions::default(),
+        );
 
         assert_eq!(result[0].converted_text, "1572213799747000000");
     }
@@ -462,7 +735,12 @@ mod tests {
         let now = datetime_from_millis(1572303922748);
         // Year 2300 is beyond the i64 nanos range (~2262)
         let far_future = Utc.with_ymd_and_hms(2300, 1, 1, 0, 0, 0).unwrap();
-        let result = convert(&far_future, &now, &[OutputFormat::Nanos], None, None);
+        let result = convert(
+            &far_future,
+            &now,
+            &[OutputFormat::Nanos],
+            &ConvertOptions::default(),
+        );
 
         assert_eq!(result[0].converted_text, "out of range");
     }
@@ -472,7 +750,15 @@ mod tests {
         let now = datetime_from_millis(1572303922748);
         let date = datetime_from_millis(1572213799747);
         let tz = crate::parse_timezone_spec("Asia/Tokyo").unwrap();
-        let result = convert(&date, &now, &[OutputFormat::Zoned], Some(tz), None);
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::Zoned],
+            &ConvertOptions {
+                display_tz: Some(tz),
+                ..ConvertOptions::default()
+            },
+        );
 
         assert!(
             result[0].converted_text.ends_with("+09:00"),
@@ -486,7 +772,15 @@ mod tests {
         let now = datetime_from_millis(1572303922748);
         let date = datetime_from_millis(1572213799747);
         let tz = crate::parse_timezone_spec("-05:00").unwrap();
-        let result = convert(&date, &now, &[OutputFormat::Zoned], Some(tz), None);
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::Zoned],
+            &ConvertOptions {
+                display_tz: Some(tz),
+                ..ConvertOptions::default()
+            },
+        );
 
         assert!(
             result[0].converted_text.ends_with("-05:00"),
@@ -499,11 +793,448 @@ mod tests {
     fn rfc3339_zoned_default_uses_local() {
         let now = datetime_from_millis(1572303922748);
         let date = datetime_from_millis(1572213799747);
-        let result = convert(&date, &now, &[OutputFormat::Zoned], None, None);
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::Zoned],
+            &ConvertOptions::default(),
+        );
 
         let expected = date
             .with_timezone(&Local)
             .to_rfc3339_opts(SecondsFormat::Millis, true);
         assert_eq!(result[0].converted_text, expected);
     }
+
+    #[test]
+    fn iso_duration_past() {
+        let now = datetime_from_millis(1572303922748);
+        let date = datetime_from_millis(1572213799747);
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::IsoDuration],
+            &ConvertOptions::default(),
+        );
+
+        assert_eq!(result[0].converted_text, "P1DT1H2M3.001S");
+    }
+
+    #[test]
+    fn iso_duration_future() {
+        let now = datetime_from_millis(1572123676746);
+        let date = datetime_from_millis(1572213799747);
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::IsoDuration],
+            &ConvertOptions::default(),
+        );
+
+        assert_eq!(result[0].converted_text, "-P1DT1H2M3.001S");
+    }
+
+    #[test]
+    fn calendar_duration_past() {
+        let now = Utc.with_ymd_and_hms(2020, 3, 1, 0, 0, 0).unwrap();
+        let date = Utc.with_ymd_and_hms(2020, 1, 31, 0, 0, 0).unwrap();
+        let tz = crate::parse_timezone_spec("UTC").unwrap();
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::CalendarDuration],
+            &ConvertOptions {
+                display_tz: Some(tz),
+                ..ConvertOptions::default()
+            },
+        );
+
+        assert_eq!(result[0].converted_text, "1month 1day ago");
+    }
+
+    #[test]
+    fn calendar_duration_future() {
+        let now = Utc.with_ymd_and_hms(2020, 1, 31, 0, 0, 0).unwrap();
+        let date = Utc.with_ymd_and_hms(2020, 3, 1, 0, 0, 0).unwrap();
+        let tz = crate::parse_timezone_spec("UTC").unwrap();
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::CalendarDuration],
+            &ConvertOptions {
+                display_tz: Some(tz),
+                ..ConvertOptions::default()
+            },
+        );
+
+        assert_eq!(result[0].converted_text, "in 1month 1day");
+    }
+
+    #[test]
+    fn calendar_duration_uses_display_tz() {
+        // In UTC this is exactly one month, but in Los Angeles it runs from February 29th
+        // 19:00 PST to March 31st 20:00 PDT
+        let now = Utc.with_ymd_and_hms(2020, 4, 1, 3, 0, 0).unwrap();
+        let date = Utc.with_ymd_and_hms(2020, 3, 1, 3, 0, 0).unwrap();
+        let tz = crate::parse_timezone_spec("America/Los_Angeles").unwrap();
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::CalendarDuration],
+            &ConvertOptions {
+                display_tz: Some(tz),
+                ..ConvertOptions::default()
+            },
+        );
+
+        assert_eq!(result[0].converted_text, "1month 2days 1h ago");
+    }
+
+    #[test]
+    fn calendar_descriptors_use_display_tz() {
+        // Tuesday 2019-12-31 in Los Angeles, already Wednesday 2020-01-01 in UTC
+        let date = Utc.with_ymd_and_hms(2020, 1, 1, 5, 0, 0).unwrap();
+        let outputs = [
+            OutputFormat::DayOfWeek,
+            OutputFormat::IsoWeek,
+            OutputFormat::Quarter,
+            OutputFormat::DayOfYear,
+        ];
+        let texts = |tz: &str| -> Vec<String> {
+            convert(
+                &date,
+                &date,
+                &outputs,
+                &ConvertOptions {
+                    display_tz: Some(crate::parse_timezone_spec(tz).unwrap()),
+                    ..ConvertOptions::default()
+                },
+            )
+            .into_iter()
+            .map(|result| result.converted_text)
+            .collect()
+        };
+
+        assert_eq!(
+            texts("America/Los_Angeles"),
+            ["Tuesday", "2020-W01", "2019-Q4", "365"]
+        );
+        assert_eq!(texts("UTC"), ["Wednesday", "2020-W01", "2020-Q1", "1"]);
+    }
+
+    #[test]
+    fn fiscal_year_and_quarter() {
+        let date = Utc.with_ymd_and_hms(2019, 11, 30, 12, 0, 0).unwrap();
+        let outputs = [OutputFormat::FiscalYear, OutputFormat::FiscalQuarter];
+        let texts = |start: Option<Month>| -> Vec<String> {
+            convert(
+                &date,
+                &date,
+                &outputs,
+                &ConvertOptions {
+                    display_tz: Some(crate::parse_timezone_spec("UTC").unwrap()),
+                    fiscal_year_start: start,
+                    ..ConvertOptions::default()
+                },
+            )
+            .into_iter()
+            .map(|result| result.converted_text)
+            .collect()
+        };
+
+        assert_eq!(texts(None), ["FY2019", "FY2019-Q4"]);
+        assert_eq!(texts(Some(Month::October)), ["FY2020", "FY2020-Q1"]);
+        assert_eq!(texts(Some(Month::April)), ["FY2020", "FY2020-Q3"]);
+        assert_eq!(texts(Some(Month::December)), ["FY2019", "FY2019-Q4"]);
+    }
+
+    #[test]
+    fn business_days_skip_weekends_and_holidays() {
+        // Thursday 2020-12-24 to Thursday 2020-12-31, with Christmas off
+        let now = Utc.with_ymd_and_hms(2020, 12, 31, 12, 0, 0).unwrap();
+        let date = Utc.with_ymd_and_hms(2020, 12, 24, 12, 0, 0).unwrap();
+        let options = ConvertOptions {
+            display_tz: Some(crate::parse_timezone_spec("UTC").unwrap()),
+            business_calendar: BusinessCalendar {
+                holidays: [NaiveDate::from_ymd_opt(2020, 12, 25).unwrap()].into(),
+                ..BusinessCalendar::default()
+            },
+            ..ConvertOptions::default()
+        };
+
+        let since = convert(&date, &now, &[OutputFormat::BusinessDays], &options);
+        assert_eq!(since[0].converted_text, "4 business days ago");
+        let until = convert(&now, &date, &[OutputFormat::BusinessDays], &options);
+        assert_eq!(until[0].converted_text, "in 4 business days");
+    }
+
+    #[test]
+    fn duration_precision_components_floor() {
+        let now = datetime_from_millis(1572303922748);
+        let date = datetime_from_millis(1572213799747);
+        let options = ConvertOptions {
+            duration_precision: DurationPrecision::Components(2),
+            ..ConvertOptions::default()
+        };
+        let result = convert(&date, &now, &[OutputFormat::Duration], &options);
+
+        assert_eq!(result[0].converted_text, "1day 1h ago");
+    }
+
+    #[test]
+    fn duration_precision_unit_round_carries() {
+        // 1h 59m 45s rounds up to 2h at minute precision
+        let now = datetime_from_millis(1572213799747 + (2 * 3600 - 15) * 1000);
+        let date = datetime_from_millis(1572213799747);
+        let options = ConvertOptions {
+            duration_precision: DurationPrecision::Minutes,
+            duration_rounding: Rounding::Round,
+            ..ConvertOptions::default()
+        };
+        let result = convert(&date, &now, &[OutputFormat::Duration], &options);
+
+        assert_eq!(result[0].converted_text, "2h ago");
+    }
+
+    #[test]
+    fn duration_precision_ceil_future() {
+        let now = datetime_from_millis(1572123676746);
+        let date = datetime_from_millis(1572213799747);
+        let options = ConvertOptions {
+            duration_precision: DurationPrecision::Hours,
+            duration_rounding: Rounding::Ceil,
+            ..ConvertOptions::default()
+        };
+        let result = convert(&date, &now, &[OutputFormat::Duration], &options);
+
+        assert_eq!(result[0].converted_text, "in 1day 2h");
+    }
+
+    #[test]
+    fn duration_precision_below_unit_is_zero() {
+        let now = datetime_from_millis(1572213799749);
+        let date = datetime_from_millis(1572213799747);
+        let options = ConvertOptions {
+            duration_precision: DurationPrecision::Days,
+            ..ConvertOptions::default()
+        };
+        let result = convert(&date, &now, &[OutputFormat::Duration], &options);
+
+        assert_eq!(result[0].converted_text, "0s ago");
+    }
+
+    #[test]
+    fn fuzzy_duration_past() {
+        let now = Utc.with_ymd_and_hms(2023, 3, 22, 19, 53, 16).unwrap();
+        let date = Utc.with_ymd_and_hms(2019, 11, 30, 21, 23, 40).unwrap();
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::FuzzyDuration],
+            &ConvertOptions::default(),
+        );
+
+        assert_eq!(result[0].converted_text, "3 years ago");
+    }
+
+    #[test]
+    fn fuzzy_duration_future_singular() {
+        let now = datetime_from_millis(1572123676746);
+        let date = datetime_from_millis(1572213799747);
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::FuzzyDuration],
+            &ConvertOptions::default(),
+        );
+
+        assert_eq!(result[0].converted_text, "in 1 day");
+    }
+
+    #[test]
+    fn fuzzy_duration_rounds() {
+        // 4m 40s
+        let now = datetime_from_millis(1572213799747 + 280_000);
+        let date = datetime_from_millis(1572213799747);
+        let options = ConvertOptions {
+            duration_rounding: Rounding::Round,
+            ..ConvertOptions::default()
+        };
+        let result = convert(&date, &now, &[OutputFormat::FuzzyDuration], &options);
+
+        assert_eq!(result[0].converted_text, "5 minutes ago");
+    }
+
+    #[test]
+    fn fuzzy_duration_just_now() {
+        let now = datetime_from_millis(1572213799749);
+        let date = datetime_from_millis(1572213799747);
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::FuzzyDuration],
+            &ConvertOptions::default(),
+        );
+
+        assert_eq!(result[0].converted_text, "just now");
+    }
+
+    #[test]
+    fn original_offset_round_trips() {
+        let now = datetime_from_millis(1572303922748);
+        let input = DateTime::parse_from_rfc3339("2019-11-30T13:27:45.123-08:00").unwrap();
+        let options = ConvertOptions {
+            original_offset: Some(*input.offset()),
+            ..ConvertOptions::default()
+        };
+        let result = convert(&input.to_utc(), &now, &[OutputFormat::Original], &options);
+        assert_eq!(result[0].converted_text, "2019-11-30T13:27:45.123-08:00");
+
+        let result = convert(
+            &input.to_utc(),
+            &now,
+            &[OutputFormat::Original],
+            &ConvertOptions::default(),
+        );
+        assert_eq!(result[0].converted_text, "2019-11-30T21:27:45.123Z");
+    }
+
+    #[test]
+    fn precision_micros_rfc3339() {
+        let now = datetime_from_millis(1572303922748);
+        let date = Utc.timestamp_nanos(1572213799747123456);
+        let tz = crate::parse_timezone_spec("-05:00").unwrap();
+        let options = ConvertOptions {
+            display_tz: Some(tz),
+            precision: Some(Precision::Micros),
+            ..ConvertOptions::default()
+        };
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::Utc, OutputFormat::Zoned],
+            &options,
+        );
+
+        assert_eq!(result[0].converted_text, "2019-10-27T22:03:19.747123Z");
+        assert_eq!(result[1].converted_text, "2019-10-27T17:03:19.747123-05:00");
+    }
+
+    #[test]
+    fn precision_secs_rfc3339() {
+        let now = datetime_from_millis(1572303922748);
+        let date = datetime_from_millis(1572213799747);
+        let options = ConvertOptions {
+            precision: Some(Precision::Secs),
+            ..ConvertOptions::default()
+        };
+        let result = convert(&date, &now, &[OutputFormat::Utc], &options);
+
+        assert_eq!(result[0].converted_text, "2019-10-27T22:03:19Z");
+    }
+
+    #[test]
+    fn precision_fractional_epoch_seconds() {
+        let now = datetime_from_millis(1572303922748);
+        let date = datetime_from_millis(1572213799747);
+        let options = ConvertOptions {
+            precision: Some(Precision::Millis),
+            ..ConvertOptions::default()
+        };
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::Seconds, OutputFormat::Millis],
+            &options,
+        );
+
+        assert_eq!(result[0].converted_text, "1572213799.747");
+        assert_eq!(result[1].converted_text, "1572213799747");
+    }
+
+    #[test]
+    fn precision_fractional_epoch_millis_nanos() {
+        let now = datetime_from_millis(1572303922748);
+        let date = Utc.timestamp_nanos(1572213799747123456);
+        let options = ConvertOptions {
+            precision: Some(Precision::Nanos),
+            ..ConvertOptions::default()
+        };
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::Millis, OutputFormat::Nanos],
+            &options,
+        );
+
+        assert_eq!(result[0].converted_text, "1572213799747.123456");
+        assert_eq!(result[1].converted_text, "1572213799747123456");
+    }
+
+    #[test]
+    fn epoch_micros_output() {
+        let now = datetime_from_millis(1572303922748);
+        let date = Utc.timestamp_nanos(1572213799747123456);
+        let result = convert(
+            &date,
+            &now,
+            &[OutputFormat::Micros],
+            &ConvertOptions::default(),
+        );
+
+        assert_eq!(result[0].converted_text, "1572213799747123");
+    }
+
+    #[test]
+    fn epoch_any_unit_with_decimals() {
+        let now = datetime_from_millis(1572303922748);
+        let date = Utc.timestamp_nanos(1572213799747123456);
+        let result = convert(
+            &date,
+            &now,
+            &[
+                OutputFormat::Epoch(EpochUnit::Seconds, 3),
+                OutputFormat::Epoch(EpochUnit::Micros, 0),
+                OutputFormat::Epoch(EpochUnit::Millis, 9),
+                OutputFormat::Epoch(EpochUnit::Nanos, 2),
+            ],
+            &ConvertOptions::default(),
+        );
+
+        assert_eq!(result[0].converted_text, "1572213799.747");
+        assert_eq!(result[1].converted_text, "1572213799747123");
+        assert_eq!(result[2].converted_text, "1572213799747.123456000");
+        assert_eq!(result[3].converted_text, "1572213799747123456.00");
+    }
+
+    #[test]
+    fn precision_fractional_epoch_negative() {
+        let now = datetime_from_millis(1572303922748);
+        let date = datetime_from_millis(-500);
+        let options = ConvertOptions {
+            precision: Some(Precision::Millis),
+            ..ConvertOptions::default()
+        };
+        let result = convert(&date, &now, &[OutputFormat::Seconds], &options);
+
+        assert_eq!(result[0].converted_text, "-0.500");
+    }
+
+    #[test]
+    fn unique_keys_suffix_repeats() {
+        let result = |format| ConversionResult {
+            converted_text: String::new(),
+            format,
+        };
+        let results = [
+            result(OutputFormat::Strftime(String::from("%d/%m"))),
+            result(OutputFormat::Millis),
+            result(OutputFormat::Strftime(String::from("%Y"))),
+            result(OutputFormat::Strftime(String::from("%H"))),
+        ];
+        assert_eq!(
+            unique_keys(&results),
+            vec!["strftime", "millis", "strftime_2", "strftime_3"]
+        );
+    }
 }
@@ -0,0 +1,855 @@
+use crate::{DurationPrecision, Rounding, TimeZoneSpec};
+use chrono::prelude::*;
+use chrono::{Days, Months, TimeDelta};
+use std::fmt::Write;
+use std::ops::Neg;
+
+const SECONDS_PER_YEAR: i64 = 31_557_600; // 365.25d, matching humantime
+const SECONDS_PER_MONTH: i64 = 2_630_016; // 30.44d, matching humantime
+
+struct HumanUnit {
+    millis: u64,
+    // humantime's abbreviation, e.g. `h`, or the word for units it spells out
+    short: &'static str,
+    pluralize_short: bool,
+    word: &'static str,
+}
+
+impl HumanUnit {
+    const fn new(
+        millis: u64,
+        short: &'static str,
+        pluralize_short: bool,
+        word: &'static str,
+    ) -> Self {
+        Self {
+            millis,
+            short,
+            pluralize_short,
+            word,
+        }
+    }
+
+    // `3years`, `1day`, `22h`
+    fn format_short(&self, value: impl Into<i64>) -> String {
+        let value = value.into();
+        if self.pluralize_short && value != 1 {
+            format!("{value}{}s", self.short)
+        } else {
+            format!("{value}{}", self.short)
+        }
+    }
+}
+
+// humantime's units, largest first
+static HUMAN_UNITS: [HumanUnit; 7] = [
+    HumanUnit::new(31_557_600_000, "year", true, "year"),
+    HumanUnit::new(2_630_016_000, "month", true, "month"),
+    HumanUnit::new(86_400_000, "day", true, "day"),
+    HumanUnit::new(3_600_000, "h", false, "hour"),
+    HumanUnit::new(60_000, "m", false, "minute"),
+    HumanUnit::new(1_000, "s", false, "second"),
+    HumanUnit::new(1, "ms", false, "millisecond"),
+];
+
+/// A signed duration split into calendar parts (months, days) and an exact remainder, so that
+/// adding `P1M` to January 31st lands at the end of February rather than 30.44 days later.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct CalendarDuration {
+    pub months: i32,
+    pub days: i64,
+    pub time: TimeDelta,
+}
+
+impl CalendarDuration {
+    /// Splits a millisecond count into years, months and days using humantime's fixed-length
+    /// units (365.25-day years, 30.44-day months), so the result lines up with `Duration` output.
+    pub fn from_fixed_millis(millis: i64) -> Self {
+        let abs_millis = millis.abs();
+        let secs = abs_millis / 1000;
+        let years = secs / SECONDS_PER_YEAR;
+        let months = (secs % SECONDS_PER_YEAR) / SECONDS_PER_MONTH;
+        let rest = (secs % SECONDS_PER_YEAR) % SECONDS_PER_MONTH;
+        let duration = Self {
+            months: i32::try_from(years * 12 + months).unwrap_or(i32::MAX),
+            days: rest / 86_400,
+            time: TimeDelta::seconds(rest % 86_400) + TimeDelta::milliseconds(abs_millis % 1000),
+        };
+        if millis.is_negative() {
+            -duration
+        } else {
+            duration
+        }
+    }
+
+    /// Counts whole calendar months, then whole days, from `start` to `end` (with `start <= end`)
+    /// on their wall-clock times, leaving the rest as exact time between the instants. Month steps
+    /// clamp to the end of shorter months, so January 31st to February 29th is one month.
+    pub fn between<Tz: TimeZone>(start: &DateTime<Tz>, end: &DateTime<Tz>) -> Self {
+        let (start_local, end_local) = (start.naive_local(), end.naive_local());
+        let mut months = (end_local.year() - start_local.year()) * 12
+            + end_local.month0().cast_signed()
+            - start_local.month0().cast_signed();
+        let mut cursor = add_months(start_local, months);
+        while months > 0 && cursor.is_none_or(|c| c > end_local) {
+            months -= 1;
+            cursor = add_months(start_local, months);
+        }
+        let cursor = cursor.unwrap_or(start_local);
+
+        let mut days = (end_local.date() - cursor.date()).num_days();
+        if days > 0 && cursor + Days::new(days.unsigned_abs()) > end_local {
+            days -= 1;
+        }
+        let cursor = cursor + Days::new(days.unsigned_abs());
+
+        // The wall-clock cursor may be repeated or skipped by a DST change, so take its earliest
+        // mapping, or the first time after a gap, and never let the rest go negative
+        let cursor = (0..=120)
+            .map(|minutes| cursor + TimeDelta::minutes(minutes))
+            .find_map(|candidate| end.timezone().from_local_datetime(&candidate).earliest())
+            .unwrap_or_else(|| start.clone());
+        Self {
+            months: months.max(0),
+            days,
+            time: (end.to_utc() - cursor.to_utc()).max(TimeDelta::zero()),
+        }
+    }
+
+    /// Multiplies every part by `factor`, or None on overflow.
+    pub fn checked_mul(self, factor: i32) -> Option<Self> {
+        Some(Self {
+            months: self.months.checked_mul(factor)?,
+            days: self.days.checked_mul(factor.into())?,
+            time: self.time.checked_mul(factor)?,
+        })
+    }
+
+    /// Flips the sign of every part, or None on overflow.
+    pub fn checked_neg(self) -> Option<Self> {
+        Some(Self {
+            months: self.months.checked_neg()?,
+            days: self.days.checked_neg()?,
+            time: -self.time,
+        })
+    }
+
+    /// Adds this duration to `instant`. Months and days are applied to the wall-clock time in
+    /// `timezone` (UTC when `None`) so they stay DST-aware; the exact part is added afterwards.
+    pub fn add_to(
+        &self,
+        instant: &DateTime<Utc>,
+        timezone: Option<TimeZoneSpec>,
+    ) -> Option<DateTime<Utc>> {
+        let local = match timezone {
+            Some(tz) => tz.utc_to_naive(instant),
+            None => instant.naive_utc(),
+        };
+        let months = Months::new(self.months.unsigned_abs());
+        let local = if self.months.is_negative() {
+            local.checked_sub_months(months)?
+        } else {
+            local.checked_add_months(months)?
+        };
+        let days = Days::new(self.days.unsigned_abs());
+        let local = if self.days.is_negative() {
+            local.checked_sub_days(days)?
+        } else {
+            local.checked_add_days(days)?
+        };
+        let shifted = match timezone {
+            Some(tz) => tz.naive_to_utc_earliest(local)?,
+            None => local.and_utc(),
+        };
+        shifted.checked_add_signed(self.time)
+    }
+
+    /// Renders in humantime's style (`3years 3months 21days 22h 29m 35s 867ms`), truncated to
+    /// milliseconds. Expects a non-negative duration.
+    pub fn to_human_string(self) -> String {
+        let values = [
+            i64::from(self.months / 12),
+            i64::from(self.months % 12),
+            self.days,
+            self.time.num_hours(),
+            self.time.num_minutes() % 60,
+            self.time.num_seconds() % 60,
+            self.time.subsec_millis().into(),
+        ];
+        let parts: Vec<String> = values
+            .into_iter()
+            .zip(&HUMAN_UNITS)
+            .filter(|(value, _)| *value != 0)
+            .map(|(value, unit)| unit.format_short(value))
+            .collect();
+        if parts.is_empty() {
+            String::from("0s")
+        } else {
+            parts.join(" ")
+        }
+    }
+
+    /// Renders as an ISO 8601 duration such as `P3Y3M21DT22H29M35.867S`. The sign of the largest
+    /// non-zero part is written once in front (`-PT1H`), and parts of the opposite sign carry their
+    /// own (`-P1MT-12H` is a month less twelve hours earlier), so the result parses back unchanged.
+    pub fn to_iso_string(self) -> String {
+        let negative = if self.months != 0 {
+            self.months < 0
+        } else if self.days != 0 {
+            self.days < 0
+        } else {
+            self.time < TimeDelta::zero()
+        };
+        let d = if negative { -self } else { self };
+
+        let date_part = designated_parts(&[
+            (i64::from(d.months / 12), 'Y'),
+            (i64::from(d.months % 12), 'M'),
+            (d.days, 'D'),
+        ]);
+
+        let seconds = d.time.num_seconds() % 60;
+        let nanos = d.time.subsec_nanos();
+        let mut time_part =
+            designated_parts(&[(d.time.num_hours(), 'H'), (d.time.num_minutes() % 60, 'M')]);
+        if seconds != 0 || nanos != 0 {
+            time_part.push_str(&format_seconds(seconds, nanos));
+        }
+
+        let sign = if negative { "-" } else { "" };
+        match (date_part.is_empty(), time_part.is_empty()) {
+            (true, true) => String::from("PT0S"),
+            (_, true) => format!("{sign}P{date_part}"),
+            _ => format!("{sign}P{date_part}T{time_part}"),
+        }
+    }
+}
+
+fn add_months(start: NaiveDateTime, months: i32) -> Option<NaiveDateTime> {
+    start.checked_add_months(Months::new(months.unsigned_abs()))
+}
+
+impl Neg for CalendarDuration {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            months: -self.months,
+            days: -self.days,
+            time: -self.time,
+        }
+    }
+}
+
+// Writes `<value><designator>` for each non-zero value, e.g. `3Y21D`.
+fn designated_parts(parts: &[(i64, char)]) -> String {
+    let mut out = String::new();
+    for (value, designator) in parts.iter().filter(|(value, _)| *value != 0) {
+        let _ = write!(out, "{value}{designator}");
+    }
+    out
+}
+
+// Formats seconds plus a fractional part with trailing zeros removed, e.g. `35.867S`.
+fn format_seconds(seconds: i64, nanos: i32) -> String {
+    if nanos == 0 {
+        return format!("{seconds}S");
+    }
+    let sign = if seconds < 0 || nanos < 0 { "-" } else { "" };
+    let fraction = format!("{:09}", nanos.unsigned_abs());
+    format!(
+        "{sign}{}.{}S",
+        seconds.unsigned_abs(),
+        fraction.trim_end_matches('0')
+    )
+}
+
+/// Formats `millis` in humantime's style (`3years 3months 21days 22h`), keeping only the units
+/// allowed by `precision` and rounding away the rest according to `rounding`.
+pub fn format_human_millis(
+    millis: u64,
+    precision: DurationPrecision,
+    rounding: Rounding,
+) -> String {
+    let smallest = match precision {
+        DurationPrecision::Components(count) => {
+            (largest_unit(millis).saturating_add(count.max(1) - 1)).min(HUMAN_UNITS.len() - 1)
+        }
+        DurationPrecision::Years => 0,
+        DurationPrecision::Months => 1,
+        DurationPrecision::Days => 2,
+        DurationPrecision::Hours => 3,
+        DurationPrecision::Minutes => 4,
+        DurationPrecision::Seconds => 5,
+        DurationPrecision::Millis => 6,
+    };
+    let rounded = round_millis(millis, HUMAN_UNITS[smallest].millis, rounding);
+    let parts: Vec<String> = decompose(rounded, smallest)
+        .into_iter()
+        .map(|(value, unit)| unit.format_short(value.cast_signed()))
+        .collect();
+    // Like humantime, nothing left is written in seconds whatever the smallest unit kept
+    if parts.is_empty() {
+        String::from("0s")
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Formats `millis` as its single largest unit, spelled out (`3 years`, `1 minute`). Returns None
+/// for durations under a second.
+pub fn format_fuzzy_millis(millis: u64, rounding: Rounding) -> Option<String> {
+    let largest = largest_unit(millis);
+    if HUMAN_UNITS[largest].millis < 1000 {
+        return None;
+    }
+    let rounded = round_millis(millis, HUMAN_UNITS[largest].millis, rounding);
+    let (value, unit) = decompose(rounded, largest).into_iter().next()?;
+    Some(if value == 1 {
+        format!("1 {}", unit.word)
+    } else {
+        format!("{value} {}s", unit.word)
+    })
+}
+
+// Index of the largest unit that fits in `millis`, or the smallest unit for zero.
+fn largest_unit(millis: u64) -> usize {
+    HUMAN_UNITS
+        .iter()
+        .position(|unit| millis >= unit.millis)
+        .unwrap_or(HUMAN_UNITS.len() - 1)
+}
+
+fn round_millis(millis: u64, quantum: u64, rounding: Rounding) -> u64 {
+    let quotient = match rounding {
+        Rounding::Floor => millis / quantum,
+        Rounding::Ceil => millis.div_ceil(quantum),
+        Rounding::Round => (millis + quantum / 2) / quantum,
+    };
+    quotient * quantum
+}
+
+// Greedily splits `millis` into humantime's units down to `HUMAN_UNITS[smallest]`, skipping zeros.
+// Any remainder below the smallest unit is dropped.
+fn decompose(mut millis: u64, smallest: usize) -> Vec<(u64, &'static HumanUnit)> {
+    HUMAN_UNITS[..=smallest]
+        .iter()
+        .filter_map(|unit| {
+            let value = millis / unit.millis;
+            millis %= unit.millis;
+            (value != 0).then_some((value, unit))
+        })
+        .collect()
+}
+
+/// Parses either an ISO 8601 duration (`P1DT2H`, `-PT30M`, `P2W`) or a humantime duration
+/// (`1h 30m`, `3days`). Humantime durations are always exact, using 30.44-day months.
+pub fn parse_duration(input: &str) -> Option<CalendarDuration> {
+    parse_iso_duration(input).or_else(|| {
+        humantime::parse_duration(input)
+            .ok()
+            .and_then(|d| TimeDelta::from_std(d).ok())
+            .map(|time| CalendarDuration {
+                time,
+                ..CalendarDuration::default()
+            })
+    })
+}
+
+/// Parses an ISO 8601 duration, or a humantime-style one (`1 month`, `2w`, `1h 30m`, `-1h`) in
+/// which, unlike `parse_duration`, years, months, weeks and days are calendar units. Unit names
+/// are case-insensitive, except that `M` is a month and `m` a minute.
+pub fn parse_calendar_duration(input: &str) -> Option<CalendarDuration> {
+    if let Some(duration) = parse_iso_duration(input) {
+        return Some(duration);
+    }
+    let input = input.trim();
+    let (negative, mut rest) = match input.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, input.strip_prefix('+').unwrap_or(input)),
+    };
+    let mut duration = CalendarDuration::default();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let digits_end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let value: i64 = rest[..digits_end].parse().ok()?;
+        let after_value = rest[digits_end..].trim_start();
+        let unit_end = after_value
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(after_value.len());
+        let unit = match &after_value[..unit_end] {
+            "M" => String::from("M"),
+            unit => unit.to_lowercase(),
+        };
+        match unit.as_str() {
+            "years" | "year" | "yr" | "yrs" | "y" => {
+                let months = i32::try_from(value.checked_mul(12)?).ok()?;
+                duration.months = duration.months.checked_add(months)?;
+            }
+            "months" | "month" | "M" => {
+                duration.months = duration.months.checked_add(i32::try_from(value).ok()?)?;
+            }
+            "weeks" | "week" | "w" => {
+                duration.days = duration.days.checked_add(value.checked_mul(7)?)?;
+            }
+            "days" | "day" | "d" => duration.days = duration.days.checked_add(value)?,
+            _ => {
+                let exact = humantime::parse_duration(&format!("{value}{unit}")).ok()?;
+                duration.time = duration
+                    .time
+                    .checked_add(&TimeDelta::from_std(exact).ok()?)?;
+            }
+        }
+        rest = after_value[unit_end..].trim_start();
+    }
+    if negative {
+        duration.checked_neg()
+    } else {
+        Some(duration)
+    }
+}
+
+fn parse_iso_duration(input: &str) -> Option<CalendarDuration> {
+    let (negative, rest) = match input.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, input.strip_prefix('+').unwrap_or(input)),
+    };
+    let rest = rest.strip_prefix(['P', 'p'])?;
+    let (date_part, time_part) = match rest.find(['T', 't']) {
+        Some(pos) => (&rest[..pos], Some(&rest[pos + 1..])),
+        None => (rest, None),
+    };
+    if date_part.is_empty() && time_part.is_none_or(str::is_empty) {
+        return None;
+    }
+
+    let mut duration = CalendarDuration::default();
+    for (value, designator) in designated_values(date_part, "YMWD")? {
+        let value: i64 = value.parse().ok()?;
+        match designator {
+            'Y' => {
+                let months = i32::try_from(value.checked_mul(12)?).ok()?;
+                duration.months = duration.months.checked_add(months)?;
+            }
+            'M' => duration.months = duration.months.checked_add(i32::try_from(value).ok()?)?,
+            'W' => duration.days = duration.days.checked_add(value.checked_mul(7)?)?,
+            _ => duration.days = duration.days.checked_add(value)?,
+        }
+    }
+    for (value, designator) in designated_values(time_part.unwrap_or_default(), "HMS")? {
+        let nanos_per_unit: i64 = match designator {
+            'H' => 3_600_000_000_000,
+            'M' => 60_000_000_000,
+            _ => 1_000_000_000,
+        };
+        let time = TimeDelta::nanoseconds(decimal_to_nanos(value, nanos_per_unit)?);
+        duration.time = duration.time.checked_add(&time)?;
+    }
+
+    if negative {
+        duration.checked_neg()
+    } else {
+        Some(duration)
+    }
+}
+
+// Splits `1Y2M3D` into `[("1", 'Y'), ("2", 'M'), ("3", 'D')]`, with the designators uppercased.
+// Returns None for trailing digits without a designator, or for designators that aren't in
+// `order` or appear out of it, as ISO 8601 allows each once, largest first.
+fn designated_values<'a>(part: &'a str, order: &str) -> Option<Vec<(&'a str, char)>> {
+    let mut values = Vec::new();
+    let mut start = 0;
+    let mut next_allowed = 0;
+    for (i, c) in part.char_indices() {
+        if c.is_ascii_alphabetic() {
+            if start == i {
+                return None;
+            }
+            let designator = c.to_ascii_uppercase();
+            let position = order[next_allowed..].find(designator)? + next_allowed;
+            next_allowed = position + 1;
+            values.push((&part[start..i], designator));
+            start = i + 1;
+        }
+    }
+    if start == part.len() {
+        Some(values)
+    } else {
+        None
+    }
+}
+
+// Converts a decimal string like `1.5` (or `1,5`, `-1.5`) multiplied by `nanos_per_unit` to
+// nanoseconds. A sign applies to the fraction as well as the whole part.
+fn decimal_to_nanos(value: &str, nanos_per_unit: i64) -> Option<i64> {
+    if let Some(magnitude) = value
+        .strip_prefix('-')
+        .filter(|magnitude| !magnitude.starts_with(['-', '+']))
+    {
+        return decimal_to_nanos(magnitude, nanos_per_unit)?.checked_neg();
+    }
+    let (whole, fraction) = match value.split_once(['.', ',']) {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (value, ""),
+    };
+    let whole: i64 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().ok()?
+    };
+    let mut nanos = whole.checked_mul(nanos_per_unit)?;
+    let mut scale = nanos_per_unit;
+    for digit in fraction.chars() {
+        scale /= 10;
+        nanos = nanos.checked_add(i64::from(digit.to_digit(10)?) * scale)?;
+    }
+    Some(nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unreadable_literal)]
+
+    use super::*;
+
+    fn datetime(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().to_utc()
+    }
+
+    #[test]
+    fn iso_string_from_fixed_millis() {
+        // The README example: 3years 3months 21days 22h 29m 35s 867ms
+        let millis = ((3 * SECONDS_PER_YEAR + 3 * SECONDS_PER_MONTH + 21 * 86_400)
+            + 22 * 3600
+            + 29 * 60
+            + 35)
+            * 1000
+            + 867;
+        assert_eq!(
+            CalendarDuration::from_fixed_millis(millis).to_iso_string(),
+            "P3Y3M21DT22H29M35.867S"
+        );
+    }
+
+    #[test]
+    fn iso_string_negative() {
+        assert_eq!(
+            CalendarDuration::from_fixed_millis(-90_123_001).to_iso_string(),
+            "-P1DT1H2M3.001S"
+        );
+    }
+
+    #[test]
+    fn iso_string_mixed_signs() {
+        let cases = [
+            (
+                CalendarDuration {
+                    months: -13,
+                    days: 2,
+                    time: TimeDelta::zero(),
+                },
+                "-P1Y1M-2D",
+            ),
+            (
+                CalendarDuration {
+                    months: -1,
+                    days: 0,
+                    time: TimeDelta::hours(12),
+                },
+                "-P1MT-12H",
+            ),
+            (
+                CalendarDuration {
+                    months: 0,
+                    days: 1,
+                    time: TimeDelta::milliseconds(-1500),
+                },
+                "P1DT-1.5S",
+            ),
+        ];
+        for (duration, iso) in cases {
+            assert_eq!(duration.to_iso_string(), iso);
+            assert_eq!(parse_duration(iso), Some(duration));
+        }
+    }
+
+    #[test]
+    fn iso_string_zero() {
+        assert_eq!(CalendarDuration::default().to_iso_string(), "PT0S");
+    }
+
+    #[test]
+    fn iso_string_date_only() {
+        let duration = CalendarDuration {
+            months: 14,
+            days: 3,
+            time: TimeDelta::zero(),
+        };
+        assert_eq!(duration.to_iso_string(), "P1Y2M3D");
+    }
+
+    #[test]
+    fn parse_iso_full() {
+        assert_eq!(
+            parse_duration("P3Y3M21DT22H29M35.867S"),
+            Some(CalendarDuration {
+                months: 39,
+                days: 21,
+                time: TimeDelta::milliseconds((22 * 3600 + 29 * 60 + 35) * 1000 + 867),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_iso_round_trips() {
+        for iso in ["P1Y2M3DT4H5M6.789S", "-PT1H", "P2D", "PT0.000001S"] {
+            assert_eq!(parse_duration(iso).unwrap().to_iso_string(), iso);
+        }
+    }
+
+    #[test]
+    fn parse_iso_weeks() {
+        assert_eq!(parse_duration("P2W").map(|d| d.days), Some(14));
+    }
+
+    #[test]
+    fn parse_iso_fractional_hours_with_comma() {
+        assert_eq!(
+            parse_duration("PT1,5H").map(|d| d.time),
+            Some(TimeDelta::minutes(90))
+        );
+    }
+
+    #[test]
+    fn parse_iso_rejects_malformed() {
+        for input in ["P", "PT", "P1", "PT1D", "P1H", "P1.5D", "PXD"] {
+            assert_eq!(parse_duration(input), None, "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_iso_negative_fraction() {
+        assert_eq!(
+            parse_duration("PT-1.5S").map(|d| d.time),
+            Some(TimeDelta::milliseconds(-1500))
+        );
+        assert_eq!(
+            parse_duration("PT-0,5S").map(|d| d.time),
+            Some(TimeDelta::milliseconds(-500))
+        );
+        assert_eq!(
+            parse_duration("-PT-1.5S").map(|d| d.time),
+            Some(TimeDelta::milliseconds(1500))
+        );
+        assert_eq!(parse_duration("PT--1.5S"), None);
+    }
+
+    #[test]
+    fn parse_iso_rejects_overflow() {
+        assert_eq!(parse_duration("P2147483647M1M"), None);
+        assert_eq!(
+            parse_duration("P2147483647M"),
+            Some(CalendarDuration {
+                months: i32::MAX,
+                ..CalendarDuration::default()
+            })
+        );
+        assert_eq!(parse_duration("P1W9223372036854775807D"), None);
+        assert_eq!(parse_duration("-P-2147483648M"), None);
+    }
+
+    #[test]
+    fn parse_iso_requires_designator_order() {
+        assert_eq!(parse_duration("P1D1D"), None);
+        assert_eq!(parse_duration("P1D1M"), None);
+        assert_eq!(parse_duration("PT1S1H"), None);
+        assert_eq!(parse_duration("PT1M1M"), None);
+        assert_eq!(
+            parse_duration("p1y2m3w4dt5h6m7s").map(|d| (d.months, d.days)),
+            Some((14, 25))
+        );
+    }
+
+    #[test]
+    fn parse_humantime() {
+        assert_eq!(
+            parse_duration("1h 30m").map(|d| d.time),
+            Some(TimeDelta::minutes(90))
+        );
+    }
+
+    #[test]
+    fn parse_calendar_humantime_units() {
+        assert_eq!(
+            parse_calendar_duration("1 month 2w 1h 30m"),
+            Some(CalendarDuration {
+                months: 1,
+                days: 14,
+                time: TimeDelta::minutes(90),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_calendar_signed_and_capitalised() {
+        assert_eq!(
+            parse_calendar_duration("-1h"),
+            Some(CalendarDuration {
+                time: TimeDelta::hours(-1),
+                ..CalendarDuration::default()
+            })
+        );
+        assert_eq!(
+            parse_calendar_duration("1 Month 2 Days"),
+            Some(CalendarDuration {
+                months: 1,
+                days: 2,
+                time: TimeDelta::zero(),
+            })
+        );
+        assert_eq!(parse_calendar_duration("3M").map(|d| d.months), Some(3));
+        assert_eq!(
+            parse_calendar_duration("+3m").map(|d| d.time),
+            Some(TimeDelta::minutes(3))
+        );
+        assert_eq!(parse_calendar_duration("--1h"), None);
+    }
+
+    #[test]
+    fn parse_calendar_rejects_overflow() {
+        assert_eq!(parse_calendar_duration("2147483647 months 1 month"), None);
+        assert_eq!(
+            parse_calendar_duration("9223372036854775807 days 1 day"),
+            None
+        );
+        assert_eq!(
+            parse_calendar_duration("9223372036854775807s 9223372036854775807s"),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_calendar_iso() {
+        assert_eq!(parse_calendar_duration("P1Y").map(|d| d.months), Some(12));
+    }
+
+    #[test]
+    fn parse_calendar_rejects_bare_number() {
+        assert_eq!(parse_calendar_duration("5"), None);
+        assert_eq!(parse_calendar_duration(""), None);
+    }
+
+    #[test]
+    fn add_month_clamps_to_month_end() {
+        let duration = parse_duration("P1M").unwrap();
+        assert_eq!(
+            duration.add_to(&datetime("2020-01-31T12:00:00Z"), None),
+            Some(datetime("2020-02-29T12:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn add_day_across_dst_keeps_wall_clock() {
+        // 2019-11-03 is the end of DST in America/Los_Angeles, a 25 hour day
+        let tz = crate::parse_timezone_spec("America/Los_Angeles").unwrap();
+        let duration = parse_duration("P1D").unwrap();
+        assert_eq!(
+            duration.add_to(&datetime("2019-11-02T12:00:00-07:00"), Some(tz)),
+            Some(datetime("2019-11-03T12:00:00-08:00"))
+        );
+    }
+
+    #[test]
+    fn subtract_exact_time() {
+        let duration = -parse_duration("PT1H30M").unwrap();
+        assert_eq!(
+            duration.add_to(&datetime("2019-11-30T21:23:40Z"), None),
+            Some(datetime("2019-11-30T19:53:40Z"))
+        );
+    }
+
+    #[test]
+    fn between_counts_calendar_months() {
+        let duration = CalendarDuration::between(
+            &datetime("2019-11-30T21:23:40.890Z"),
+            &datetime("2023-03-22T19:53:16.757Z"),
+        );
+        assert_eq!(
+            duration.to_human_string(),
+            "3years 3months 21days 22h 29m 35s 867ms"
+        );
+    }
+
+    #[test]
+    fn between_clamps_to_month_end() {
+        let duration = CalendarDuration::between(
+            &datetime("2020-01-31T00:00:00Z"),
+            &datetime("2020-03-01T00:00:00Z"),
+        );
+        assert_eq!(duration.to_human_string(), "1month 1day");
+    }
+
+    #[test]
+    fn between_partial_month_is_days() {
+        let duration = CalendarDuration::between(
+            &datetime("2020-01-15T12:00:00Z"),
+            &datetime("2020-02-14T11:00:00Z"),
+        );
+        assert_eq!(duration.to_human_string(), "29days 23h");
+    }
+
+    #[test]
+    fn between_equal_is_zero() {
+        let duration = CalendarDuration::between(
+            &datetime("2020-01-15T12:00:00Z"),
+            &datetime("2020-01-15T12:00:00Z"),
+        );
+        assert_eq!(duration.to_human_string(), "0s");
+    }
+
+    #[test]
+    fn between_across_dst_end_counts_exact_time() {
+        // 01:00-02:00 happens twice in Los Angeles on 2019-11-03
+        let tz: chrono_tz::Tz = "America/Los_Angeles".parse().unwrap();
+        let local = |s: &str| datetime(s).with_timezone(&tz);
+        let duration = CalendarDuration::between(
+            &local("2019-11-03T00:30:00-07:00"),
+            &local("2019-11-03T01:30:00-08:00"),
+        );
+        assert_eq!(duration.to_human_string(), "2h");
+        let duration = CalendarDuration::between(
+            &local("2019-11-02T01:30:00-07:00"),
+            &local("2019-11-03T01:30:00-08:00"),
+        );
+        assert_eq!(duration.to_human_string(), "1day 1h");
+        let duration = CalendarDuration::between(
+            &local("2019-11-02T12:00:00-07:00"),
+            &local("2019-11-03T12:00:00-08:00"),
+        );
+        assert_eq!(duration.to_human_string(), "1day");
+    }
+
+    #[test]
+    fn human_millis_with_any_component_count() {
+        assert_eq!(
+            format_human_millis(
+                90_061_001,
+                DurationPrecision::Components(usize::MAX),
+                Rounding::Floor
+            ),
+            "1day 1h 1m 1s 1ms"
+        );
+    }
+
+    #[test]
+    fn human_millis_below_smallest_unit_is_zero_seconds() {
+        assert_eq!(
+            format_human_millis(5_000, DurationPrecision::Days, Rounding::Floor),
+            "0s"
+        );
+    }
+}
@@ -1,17 +1,83 @@
 use crate::converting::ConversionResult;
+use crate::parsing::ParsedInput;
+use crate::records::Record;
+use chrono::format::{Item, StrftimeItems};
 use chrono::prelude::*;
 use chrono_tz::Tz;
 use clap::ValueEnum;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::{env, fmt, fs, iter, mem};
 
 mod alfred;
+mod business;
 mod converting;
+mod duration;
 mod parsing;
+mod ranging;
+mod records;
+mod relative;
+mod repl;
+mod shell;
+mod snapping;
+mod templating;
+mod watching;
 
 pub enum OutputMode {
     ValuePerLine,
+    /// Each value prefixed with its label, e.g. `Epoch Millis: 1575149020890`.
+    Labelled,
+    /// Labels and values in aligned columns.
+    Table,
     Alfred,
+    /// Variable assignments for `shell` to evaluate, each named `prefix` followed by the output key.
+    Shell {
+        shell: Shell,
+        prefix: String,
+    },
+    /// One line per input rendered from a template.
+    Template(Template),
+    /// A JSON record of each input on its own line.
+    Ndjson,
+    /// A YAML list with a record of each input.
+    Yaml,
+    /// A TOML array of tables with a record of each input.
+    Toml,
 }
 
+/// Structured formats for `--records`, each holding a record per input of its outputs or error.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, ValueEnum)]
+pub enum RecordFormat {
+    Ndjson,
+    Yaml,
+    Toml,
+}
+
+impl From<RecordFormat> for OutputMode {
+    fn from(format: RecordFormat) -> Self {
+        match format {
+            RecordFormat::Ndjson => OutputMode::Ndjson,
+            RecordFormat::Yaml => OutputMode::Yaml,
+            RecordFormat::Toml => OutputMode::Toml,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    #[value(alias = "pwsh")]
+    Powershell,
+}
+
+/// Prefix of the variable names in shell output when `--prefix` is not given.
+pub const DEFAULT_SHELL_PREFIX: &str = "TT_";
+
 #[derive(PartialEq, Eq, Debug, Clone, Copy, ValueEnum)]
 pub enum DurationUnit {
     Milliseconds,
@@ -35,18 +101,401 @@ pub enum EpochUnit {
     Nanos,
 }
 
+/// Sub-second precision for RFC3339 and fractional epoch outputs. `Auto` uses whatever precision
+/// the input was written with.
 #[derive(PartialEq, Eq, Debug, Clone, Copy, ValueEnum)]
+pub enum Precision {
+    #[value(alias = "s")]
+    Secs,
+    #[value(alias = "ms")]
+    Millis,
+    #[value(alias = "us")]
+    Micros,
+    #[value(alias = "ns")]
+    Nanos,
+    Auto,
+}
+
+impl Precision {
+    /// Resolves `Auto` to the precision of the input.
+    #[must_use]
+    pub fn resolve(self, input_precision: Precision) -> Precision {
+        match self {
+            Precision::Auto => input_precision,
+            other => other,
+        }
+    }
+
+    /// The precision implied by a count of written fractional-second digits.
+    #[must_use]
+    pub fn from_subsec_digits(digits: usize) -> Precision {
+        match digits {
+            0 => Precision::Secs,
+            1..=3 => Precision::Millis,
+            4..=6 => Precision::Micros,
+            _ => Precision::Nanos,
+        }
+    }
+
+    #[must_use]
+    pub fn seconds_format(self) -> SecondsFormat {
+        match self {
+            Precision::Secs => SecondsFormat::Secs,
+            Precision::Millis => SecondsFormat::Millis,
+            Precision::Micros => SecondsFormat::Micros,
+            Precision::Nanos => SecondsFormat::Nanos,
+            Precision::Auto => SecondsFormat::AutoSi,
+        }
+    }
+}
+
+impl From<EpochUnit> for Precision {
+    fn from(unit: EpochUnit) -> Self {
+        match unit {
+            EpochUnit::Seconds => Precision::Secs,
+            EpochUnit::Millis => Precision::Millis,
+            EpochUnit::Micros => Precision::Micros,
+            EpochUnit::Nanos => Precision::Nanos,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, ValueEnum)]
+pub enum ByteOrder {
+    #[value(alias = "big")]
+    Be,
+    #[value(alias = "little")]
+    Le,
+}
+
+/// The date system of an Excel serial date. Serials count days (with the time of day as a
+/// fraction) from 1900-01-01 = 1 on Windows, or 1904-01-01 = 0 in the old Mac system.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, ValueEnum)]
+pub enum ExcelSystem {
+    #[value(name = "1900")]
+    From1900,
+    #[value(name = "1904")]
+    From1904,
+}
+
+/// Options controlling how an input string is interpreted as an instant.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Forces numeric input to be read as an epoch in this unit instead of inferring it.
+    pub epoch_unit: Option<EpochUnit>,
+    /// Zone assumed for inputs lacking explicit zone info; UTC when `None`.
+    pub input_timezone: Option<TimeZoneSpec>,
+    /// When set, the input is decoded as a raw 4- or 8-byte hex dump in this byte order.
+    pub byte_order: Option<ByteOrder>,
+    /// When set, the input is read as an Excel serial date in this date system, in the input
+    /// timezone.
+    pub excel: Option<ExcelSystem>,
+    /// Date a bare time of day falls on; today in the input timezone when `None`.
+    pub anchor: Option<NaiveDate>,
+    /// Completes a bare date to the last instant of the day instead of midnight.
+    pub end_of_day: bool,
+    /// Reports on stderr which anchor, if any, completed the input.
+    pub explain: bool,
+    /// Days skipped by `+Nbd` / `-Nbd` arithmetic.
+    pub business_calendar: BusinessCalendar,
+    /// Year for inputs written without one, such as syslog timestamps; otherwise the most recent
+    /// year that puts the input in the past.
+    pub year: Option<i32>,
+    /// strftime patterns tried, in order, before the built-in formats.
+    pub input_formats: Vec<String>,
+}
+
+/// Which days count as business days: every day that is not a weekend day or a holiday.
+#[derive(Debug, Clone)]
+pub struct BusinessCalendar {
+    pub weekend: Vec<Weekday>,
+    pub holidays: BTreeSet<NaiveDate>,
+}
+
+impl Default for BusinessCalendar {
+    fn default() -> Self {
+        BusinessCalendar {
+            weekend: vec![Weekday::Sat, Weekday::Sun],
+            holidays: BTreeSet::new(),
+        }
+    }
+}
+
+impl BusinessCalendar {
+    #[must_use]
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        !self.weekend.contains(&date.weekday()) && !self.holidays.contains(&date)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, ValueEnum)]
 pub enum OutputFormat {
     Utc,
     Zoned,
+    Original,
     Seconds,
     Millis,
+    Micros,
     Nanos,
     Duration,
+    FuzzyDuration,
+    IsoDuration,
+    CalendarDuration,
+    BusinessDays,
+    DayOfWeek,
+    IsoWeek,
+    Quarter,
+    FiscalYear,
+    FiscalQuarter,
+    DayOfYear,
     #[value(skip)]
     DurationSinceUnits(DurationUnit),
+    /// An epoch in any unit with a fixed number of decimal places, e.g. `seconds:3`.
+    #[value(skip)]
+    Epoch(EpochUnit, u8),
+    /// A strftime pattern such as `%F %T`, rendered in the display timezone.
+    #[value(skip)]
+    Strftime(String),
+}
+
+impl OutputFormat {
+    /// A stable identifier such as `utc` or `fuzzy_duration`, used for Alfred `uid`s.
+    #[must_use]
+    pub fn key(&self) -> String {
+        match self {
+            OutputFormat::Utc => String::from("utc"),
+            OutputFormat::Zoned => String::from("zoned"),
+            OutputFormat::Original => String::from("original"),
+            OutputFormat::Seconds => String::from("seconds"),
+            OutputFormat::Millis => String::from("millis"),
+            OutputFormat::Micros => String::from("micros"),
+            OutputFormat::Nanos => String::from("nanos"),
+            OutputFormat::Duration => String::from("duration"),
+            OutputFormat::FuzzyDuration => String::from("fuzzy_duration"),
+            OutputFormat::IsoDuration => String::from("iso_duration"),
+            OutputFormat::CalendarDuration => String::from("calendar_duration"),
+            OutputFormat::BusinessDays => String::from("business_days"),
+            OutputFormat::DayOfWeek => String::from("day_of_week"),
+            OutputFormat::IsoWeek => String::from("iso_week"),
+            OutputFormat::Quarter => String::from("quarter"),
+            OutputFormat::FiscalYear => String::from("fiscal_year"),
+            OutputFormat::FiscalQuarter => String::from("fiscal_quarter"),
+            OutputFormat::DayOfYear => String::from("day_of_year"),
+            OutputFormat::DurationSinceUnits(duration_unit) => {
+                format!("duration_since_{duration_unit:?}").to_lowercase()
+            }
+            OutputFormat::Epoch(epoch_unit, decimals) => {
+                format!("epoch_{epoch_unit:?}_{decimals}").to_lowercase()
+            }
+            OutputFormat::Strftime(_) => String::from("strftime"),
+        }
+    }
+
+    /// The format whose `key` is `key`. Strftime outputs have no key of their own to look up.
+    #[must_use]
+    pub fn from_key(key: &str) -> Option<Self> {
+        if let Some(unit) = key.strip_prefix("duration_since_") {
+            return DurationUnit::from_str(unit, false)
+                .ok()
+                .map(OutputFormat::DurationSinceUnits);
+        }
+        if let Some((unit, decimals)) = key
+            .strip_prefix("epoch_")
+            .and_then(|epoch| epoch.rsplit_once('_'))
+        {
+            let unit = EpochUnit::from_str(unit, false).ok()?;
+            let decimals = decimals.parse().ok().filter(|d| *d <= 9)?;
+            return Some(OutputFormat::Epoch(unit, decimals));
+        }
+        OutputFormat::value_variants()
+            .iter()
+            .find(|format| format.key() == key)
+            .cloned()
+    }
+
+    /// A descriptive name such as `RFC3339 - UTC` or `Epoch Millis`, used for Alfred subtitles and
+    /// labelled output.
+    #[must_use]
+    pub fn label(&self) -> String {
+        match self {
+            OutputFormat::Utc => String::from("RFC3339 - UTC"),
+            OutputFormat::Zoned => String::from("RFC3339 - Zoned"),
+            OutputFormat::Original => String::from("RFC3339 - Original Offset"),
+            OutputFormat::Seconds => String::from("Epoch Seconds"),
+            OutputFormat::Millis => String::from("Epoch Millis"),
+            OutputFormat::Micros => String::from("Epoch Microseconds"),
+            OutputFormat::Nanos => String::from("Epoch Nanoseconds"),
+            OutputFormat::Duration => String::from("Duration"),
+            OutputFormat::FuzzyDuration => String::from("Fuzzy Duration"),
+            OutputFormat::IsoDuration => String::from("ISO 8601 Duration"),
+            OutputFormat::CalendarDuration => String::from("Calendar Duration"),
+            OutputFormat::BusinessDays => String::from("Business Days"),
+            OutputFormat::DayOfWeek => String::from("Day of Week"),
+            OutputFormat::IsoWeek => String::from("ISO Week"),
+            OutputFormat::Quarter => String::from("Calendar Quarter"),
+            OutputFormat::FiscalYear => String::from("Fiscal Year"),
+            OutputFormat::FiscalQuarter => String::from("Fiscal Quarter"),
+            OutputFormat::DayOfYear => String::from("Day of Year"),
+            OutputFormat::DurationSinceUnits(duration_unit) => {
+                format!("Duration {duration_unit:?}")
+            }
+            OutputFormat::Epoch(epoch_unit, decimals) => {
+                format!("Epoch {epoch_unit:?} ({decimals} decimals)")
+            }
+            OutputFormat::Strftime(pattern) => format!("Format {pattern}"),
+        }
+    }
 }
 
+impl fmt::Display for OutputFormat {
+    /// Writes the name accepted by `--outputs`, or by `--duration-unit` for an appended duration.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::DurationSinceUnits(unit) => write!(f, "{}", value_name(unit)),
+            OutputFormat::Epoch(unit, decimals) => write!(f, "{}:{decimals}", value_name(unit)),
+            OutputFormat::Strftime(pattern) => f.write_str(pattern),
+            _ => f.write_str(&value_name(self)),
+        }
+    }
+}
+
+/// The name clap accepts for `value`, e.g. `fuzzy-duration` for `OutputFormat::FuzzyDuration`.
+///
+/// # Panics
+///
+/// Panics if `value` is a variant skipped by clap.
+#[must_use]
+pub fn value_name(value: &impl ValueEnum) -> String {
+    value
+        .to_possible_value()
+        .expect("variant is not skipped")
+        .get_name()
+        .to_string()
+}
+
+/// How much of a human-readable duration to keep: either a number of the largest non-zero
+/// components, or everything down to a fixed unit.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum DurationPrecision {
+    Components(usize),
+    Years,
+    Months,
+    Days,
+    Hours,
+    Minutes,
+    Seconds,
+    #[default]
+    Millis,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, ValueEnum)]
+pub enum Rounding {
+    #[default]
+    Floor,
+    Round,
+    Ceil,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, ValueEnum)]
+pub enum CalendarUnit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, ValueEnum)]
+pub enum WeekStart {
+    #[default]
+    #[value(alias = "iso")]
+    Monday,
+    Sunday,
+}
+
+/// Moves the parsed instant to a calendar boundary before it is converted.
+#[derive(Debug, Clone, Copy)]
+pub struct Snap {
+    pub rounding: Rounding,
+    pub unit: CalendarUnit,
+    pub week_start: WeekStart,
+    /// Zone whose calendar defines the boundaries; UTC when `None`.
+    pub timezone: Option<TimeZoneSpec>,
+}
+
+/// The ends and step of a generated range. Both ends are parsed like a normal input; `step` is
+/// a humantime or ISO 8601 duration whose calendar parts follow `timezone` (UTC when `None`).
+#[derive(Debug, Clone, Copy)]
+pub struct RangeSpec<'a> {
+    pub start: &'a str,
+    pub end: &'a str,
+    pub step: &'a str,
+    pub timezone: Option<TimeZoneSpec>,
+}
+
+/// A line of text with `{placeholder}`s (or Handlebars-style `{{placeholder}}`s) naming outputs
+/// by their key, e.g. `{utc} ({millis}) {duration}`, plus `{input}` and `{zone}`. `\{` and `\}`
+/// are literal braces.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Template {
+    text: String,
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+enum TemplatePart {
+    Text(String),
+    Output(OutputFormat),
+    Input,
+    Zone,
+}
+
+impl Template {
+    /// The outputs the template refers to, each once, in the order they first appear.
+    #[must_use]
+    pub fn outputs(&self) -> Vec<OutputFormat> {
+        let mut outputs: Vec<OutputFormat> = Vec::new();
+        for part in &self.parts {
+            if let TemplatePart::Output(format) = part
+                && !outputs.contains(format)
+            {
+                outputs.push(format.clone());
+            }
+        }
+        outputs
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Options controlling how a parsed instant is rendered.
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    /// Zone used for zoned and calendar outputs; system local when `None`.
+    pub display_tz: Option<TimeZoneSpec>,
+    /// Appends a duration in this fixed unit after the selected outputs.
+    pub extra_duration_unit: Option<DurationUnit>,
+    pub duration_precision: DurationPrecision,
+    pub duration_rounding: Rounding,
+    /// Sub-second precision for RFC3339 outputs, which also adds a fractional part to epoch
+    /// outputs. Millis RFC3339 and whole epochs when `None`.
+    pub precision: Option<Precision>,
+    /// Days counted by the business-days output.
+    pub business_calendar: BusinessCalendar,
+    /// First month of the fiscal year; January when `None`.
+    pub fiscal_year_start: Option<Month>,
+    /// Offset the input was written in, used by the original output; UTC when `None`.
+    pub original_offset: Option<FixedOffset>,
+}
+
+/// Outputs of the built-in default profile.
 pub const DEFAULT_OUTPUTS: &[OutputFormat] = &[
     OutputFormat::Utc,
     OutputFormat::Zoned,
@@ -54,7 +503,23 @@ pub const DEFAULT_OUTPUTS: &[OutputFormat] = &[
     OutputFormat::Duration,
 ];
 
-#[derive(Debug, Clone, Copy)]
+/// Outputs of the built-in alfred profile, which adds the short fuzzy duration to the Alfred list.
+pub const ALFRED_OUTPUTS: &[OutputFormat] = &[
+    OutputFormat::Utc,
+    OutputFormat::Zoned,
+    OutputFormat::Millis,
+    OutputFormat::FuzzyDuration,
+    OutputFormat::Duration,
+];
+
+/// Outputs refreshed by `watch` without an input.
+pub const WATCH_CLOCK_OUTPUTS: &[OutputFormat] =
+    &[OutputFormat::Utc, OutputFormat::Zoned, OutputFormat::Millis];
+
+/// Outputs refreshed by `watch` counting down to, or up from, a fixed input.
+pub const WATCH_COUNTDOWN_OUTPUTS: &[OutputFormat] = &[OutputFormat::Duration];
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum TimeZoneSpec {
     Named(Tz),
     Fixed(FixedOffset),
@@ -71,19 +536,215 @@ impl TimeZoneSpec {
         }
     }
 
+    /// Like `naive_to_utc`, but resolves ambiguous wall-clock times (a DST fall-back hour) to the
+    /// earlier instant instead of failing.
+    #[must_use]
+    pub fn naive_to_utc_earliest(self, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            TimeZoneSpec::Named(tz) => tz
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|d| d.to_utc()),
+            TimeZoneSpec::Fixed(off) => off
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|d| d.to_utc()),
+        }
+    }
+
+    #[must_use]
+    pub fn offset_at(self, dt: &DateTime<Utc>) -> FixedOffset {
+        match self {
+            TimeZoneSpec::Named(tz) => dt.with_timezone(&tz).offset().fix(),
+            TimeZoneSpec::Fixed(off) => off,
+        }
+    }
+
+    #[must_use]
+    pub fn utc_to_naive(self, dt: &DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            TimeZoneSpec::Named(tz) => dt.with_timezone(&tz).naive_local(),
+            TimeZoneSpec::Fixed(off) => dt.with_timezone(&off).naive_local(),
+        }
+    }
+
     #[must_use]
     pub fn format_rfc3339_millis(self, dt: &DateTime<Utc>) -> String {
+        self.format_rfc3339(dt, SecondsFormat::Millis)
+    }
+
+    #[must_use]
+    pub fn format(self, dt: &DateTime<Utc>, pattern: &str) -> String {
         match self {
-            TimeZoneSpec::Named(tz) => dt
-                .with_timezone(&tz)
-                .to_rfc3339_opts(SecondsFormat::Millis, true),
-            TimeZoneSpec::Fixed(off) => dt
-                .with_timezone(&off)
-                .to_rfc3339_opts(SecondsFormat::Millis, true),
+            TimeZoneSpec::Named(tz) => dt.with_timezone(&tz).format(pattern).to_string(),
+            TimeZoneSpec::Fixed(off) => dt.with_timezone(&off).format(pattern).to_string(),
+        }
+    }
+
+    #[must_use]
+    pub fn format_rfc3339(self, dt: &DateTime<Utc>, seconds_format: SecondsFormat) -> String {
+        match self {
+            TimeZoneSpec::Named(tz) => dt.with_timezone(&tz).to_rfc3339_opts(seconds_format, true),
+            TimeZoneSpec::Fixed(off) => {
+                dt.with_timezone(&off).to_rfc3339_opts(seconds_format, true)
+            }
         }
     }
 }
 
+impl fmt::Display for TimeZoneSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeZoneSpec::Named(tz) => f.write_str(tz.name()),
+            TimeZoneSpec::Fixed(off) => write!(f, "{off}"),
+        }
+    }
+}
+
+/// Defaults read from a `config.toml`. Settings use the long flag names and are written as they
+/// would be on the command line, e.g. `output-timezone = "America/Los_Angeles"` or
+/// `outputs = ["utc", "zoned"]`. The `[zones]` table defines aliases that can be used wherever a
+/// timezone is expected.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    pub alfred: Option<bool>,
+    pub labels: Option<bool>,
+    pub table: Option<bool>,
+    pub records: Option<String>,
+    pub shell: Option<String>,
+    pub prefix: Option<String>,
+    pub template: Option<String>,
+    pub template_file: Option<PathBuf>,
+    pub duration_unit: Option<String>,
+    pub duration_precision: Option<String>,
+    pub duration_rounding: Option<String>,
+    pub epoch_unit: Option<String>,
+    pub byte_order: Option<String>,
+    pub excel: Option<String>,
+    pub input_timezone: Option<String>,
+    pub output_timezone: Option<String>,
+    pub anchor: Option<String>,
+    pub end_of_day: Option<bool>,
+    pub explain: Option<bool>,
+    pub weekend: Option<Vec<String>>,
+    pub holidays: Option<String>,
+    pub fiscal_year_start: Option<String>,
+    pub year: Option<i32>,
+    pub precision: Option<String>,
+    pub floor: Option<String>,
+    pub ceil: Option<String>,
+    pub round: Option<String>,
+    pub week_start: Option<String>,
+    pub outputs: Option<Vec<String>>,
+    pub input_format: Option<Vec<String>>,
+    /// Profile used when `--profile` isn't given.
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    #[serde(default)]
+    pub zones: BTreeMap<String, String>,
+}
+
+impl Config {
+    /// Looks up a profile defined in the config file, falling back to the built-in `default`
+    /// profile (the `DEFAULT_OUTPUTS`), `alfred` profile (the `ALFRED_OUTPUTS`) and `all` profile
+    /// (every named output).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error string if there is no profile called `name`.
+    pub fn profile(&self, name: &str) -> Result<Profile, String> {
+        if let Some(profile) = self.profiles.get(name) {
+            return Ok(profile.clone());
+        }
+        let outputs = match name {
+            DEFAULT_PROFILE => DEFAULT_OUTPUTS,
+            ALFRED_PROFILE => ALFRED_OUTPUTS,
+            "all" => OutputFormat::value_variants(),
+            _ => return Err(format!("Unknown profile: {name}")),
+        };
+        Ok(Profile {
+            outputs: Some(outputs.iter().map(ToString::to_string).collect()),
+            ..Profile::default()
+        })
+    }
+
+    /// The profile filling in whatever the selected profile and the config file left unset:
+    /// `alfred` in Alfred mode and `default` otherwise, unless `watch` is running with the built-in
+    /// one, whose outputs would hide the watch defaults.
+    #[must_use]
+    pub fn fallback_profile(&self, alfred: bool, watching: bool) -> Profile {
+        let name = if alfred {
+            ALFRED_PROFILE
+        } else {
+            DEFAULT_PROFILE
+        };
+        if watching && !self.profiles.contains_key(name) {
+            return Profile::default();
+        }
+        self.profile(name).unwrap_or_default()
+    }
+}
+
+/// A named set of outputs, with the timezones and precision to render them in, selected with
+/// `--profile`. Settings are written the same way as at the top level of the config file, e.g.
+/// `[profiles.sql]` with `outputs = ["%F %T"]` and `output-timezone = "UTC"`.
+#[derive(PartialEq, Eq, Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Profile {
+    pub outputs: Option<Vec<String>>,
+    pub input_timezone: Option<String>,
+    pub output_timezone: Option<String>,
+    pub precision: Option<String>,
+}
+
+/// The profile used when none is selected. Unless the config file redefines it, it produces the
+/// `DEFAULT_OUTPUTS`.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// The profile used in Alfred mode when none is selected. Unless the config file redefines it, it
+/// produces the `ALFRED_OUTPUTS`.
+pub const ALFRED_PROFILE: &str = "alfred";
+
+/// `$XDG_CONFIG_HOME/timeturner/config.toml`, with `XDG_CONFIG_HOME` defaulting to `~/.config`.
+#[must_use]
+pub fn default_config_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .map(|dir| dir.join("timeturner").join("config.toml"))
+}
+
+/// Reads and parses a config file.
+///
+/// # Errors
+///
+/// Returns an error string if the file cannot be read, is not valid TOML or has unknown keys.
+pub fn load_config(path: &Path) -> Result<Config, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read config file {}: {err}", path.display()))?;
+    parse_config(&text).map_err(|err| format!("Invalid config file {}: {err}", path.display()))
+}
+
+fn parse_config(text: &str) -> Result<Config, String> {
+    toml::from_str(text).map_err(|err| err.message().to_string())
+}
+
+/// Parses a timezone like `parse_timezone_spec`, after replacing a configured alias (e.g. `pdx`)
+/// with the zone it stands for.
+///
+/// # Errors
+///
+/// Returns an error string if the input, or the zone its alias names, is not a valid timezone.
+pub fn resolve_timezone(
+    s: &str,
+    aliases: &BTreeMap<String, String>,
+) -> Result<TimeZoneSpec, String> {
+    parse_timezone_spec(aliases.get(s).map_or(s, String::as_str))
+}
+
 /// Parses an IANA timezone name (e.g. `"America/New_York"`) or a fixed UTC offset
 /// (e.g. `"+05:30"`, `"-08:00"`, `"+0530"`, `"-0800"`, `"Z"`, `"UTC"`) into a `TimeZoneSpec`.
 ///
@@ -106,6 +767,192 @@ pub fn parse_timezone_spec(s: &str) -> Result<TimeZoneSpec, String> {
         .map_err(|_| format!("Unknown timezone: {s}"))
 }
 
+/// Parses a `--prefix` value, which must keep the variable names valid in every shell: letters,
+/// digits and underscores, not starting with a digit.
+///
+/// # Errors
+///
+/// Returns an error string if the prefix contains any other character or starts with a digit.
+pub fn parse_shell_prefix(s: &str) -> Result<String, String> {
+    if s.starts_with(|c: char| c.is_ascii_digit())
+        || !s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(format!(
+            "Invalid prefix: {s}, use letters, digits and underscores not starting with a digit"
+        ));
+    }
+    Ok(String::from(s))
+}
+
+/// Parses a `--duration-precision` value: either a count of components (e.g. `"2"`) or the
+/// smallest unit to show (`years`, `months`, `days`, `hours`/`h`, `minutes`/`m`, `seconds`/`s`,
+/// `millis`/`ms`).
+///
+/// # Errors
+///
+/// Returns an error string if the input is neither a positive count nor a known unit.
+pub fn parse_duration_precision(s: &str) -> Result<DurationPrecision, String> {
+    if let Ok(count) = s.parse::<usize>() {
+        return if count > 0 {
+            Ok(DurationPrecision::Components(count))
+        } else {
+            Err(String::from(
+                "Duration precision must be at least 1 component",
+            ))
+        };
+    }
+    match s.to_ascii_lowercase().as_str() {
+        "year" | "years" | "y" => Ok(DurationPrecision::Years),
+        "month" | "months" => Ok(DurationPrecision::Months),
+        "day" | "days" | "d" => Ok(DurationPrecision::Days),
+        "hour" | "hours" | "h" => Ok(DurationPrecision::Hours),
+        "minute" | "minutes" | "m" => Ok(DurationPrecision::Minutes),
+        "second" | "seconds" | "s" => Ok(DurationPrecision::Seconds),
+        "milli" | "millis" | "ms" => Ok(DurationPrecision::Millis),
+        _ => Err(format!("Unknown duration precision: {s}")),
+    }
+}
+
+/// Loads holiday dates for business-day calculations from an iCalendar (`.ics`) file or a plain
+/// text file listing one `YYYY-MM-DD` date per line.
+///
+/// # Errors
+///
+/// Returns an error string if the file cannot be read or contains an invalid date.
+pub fn load_holidays(path: &str) -> Result<BTreeSet<NaiveDate>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read holidays file {path}: {err}"))?;
+    business::parse_holidays(&text)
+}
+
+/// Parses an `--outputs` value: any of the named formats (e.g. `"utc"`, `"millis"`), an epoch
+/// unit with a number of decimal places such as `"seconds:3"` or `"us:0"`, or a strftime pattern
+/// containing `%` such as `"%F %T"`.
+///
+/// # Errors
+///
+/// Returns an error string if the input is not a known format, the decimal places exceed 9, or
+/// the strftime pattern is invalid.
+pub fn parse_output_format(s: &str) -> Result<OutputFormat, String> {
+    if let Ok(format) = OutputFormat::from_str(s, true) {
+        return Ok(format);
+    }
+    if s.contains('%') {
+        if StrftimeItems::new(s).any(|item| item == Item::Error) {
+            return Err(format!("Invalid strftime pattern: {s}"));
+        }
+        return Ok(OutputFormat::Strftime(s.to_string()));
+    }
+    let (unit, decimals) = s
+        .split_once(':')
+        .ok_or_else(|| format!("Unknown output format: {s}"))?;
+    let unit =
+        EpochUnit::from_str(unit, true).map_err(|_| format!("Unknown epoch unit: {unit}"))?;
+    let decimals: u8 = decimals
+        .parse()
+        .ok()
+        .filter(|d| *d <= 9)
+        .ok_or_else(|| format!("Decimal places must be between 0 and 9: {decimals}"))?;
+    Ok(OutputFormat::Epoch(unit, decimals))
+}
+
+/// Parses an `--input-format` value: a strftime pattern containing `%` such as
+/// `"%d/%m/%Y %H:%M"`.
+///
+/// # Errors
+///
+/// Returns an error string if the value contains no `%` or the strftime pattern is invalid.
+pub fn parse_input_format(s: &str) -> Result<String, String> {
+    if !s.contains('%') || StrftimeItems::new(s).any(|item| item == Item::Error) {
+        return Err(format!("Invalid strftime pattern: {s}"));
+    }
+    Ok(s.to_string())
+}
+
+/// Parses a `--template` value. Placeholders may name an output by its key (`fuzzy_duration`,
+/// `epoch_millis_3`) or as it would be given to `--outputs` (`fuzzy-duration`, `millis:3`,
+/// `%F %T`), or be `input` for the input as written or `zone` for the output timezone.
+///
+/// # Errors
+///
+/// Returns an error string naming the first placeholder that is unknown or left unclosed.
+pub fn parse_template(s: &str) -> Result<Template, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut rest = s;
+    while let Some(pos) = rest.find(['{', '\\']) {
+        text.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        if rest[pos..].starts_with('\\') {
+            if let Some(brace @ ('{' | '}')) = after.chars().next() {
+                text.push(brace);
+                rest = &after[1..];
+            } else {
+                text.push('\\');
+                rest = after;
+            }
+            continue;
+        }
+        let (name, remainder) = match after.strip_prefix('{') {
+            Some(inner) => inner.split_once("}}"),
+            None => after.split_once('}'),
+        }
+        .ok_or_else(|| format!("Unclosed placeholder in template: {}", &rest[pos..]))?;
+        if !text.is_empty() {
+            parts.push(TemplatePart::Text(mem::take(&mut text)));
+        }
+        parts.push(template_placeholder(name.trim())?);
+        rest = remainder;
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        parts.push(TemplatePart::Text(text));
+    }
+    Ok(Template {
+        text: s.to_string(),
+        parts,
+    })
+}
+
+fn template_placeholder(name: &str) -> Result<TemplatePart, String> {
+    match name {
+        "input" => Ok(TemplatePart::Input),
+        "zone" => Ok(TemplatePart::Zone),
+        _ if name.contains('%') => parse_output_format(name).map(TemplatePart::Output),
+        _ => OutputFormat::from_key(name)
+            .map_or_else(|| parse_output_format(name), Ok)
+            .map(TemplatePart::Output)
+            .map_err(|_| format!("Unknown placeholder in template: {{{name}}}")),
+    }
+}
+
+/// Reads a template from a file, dropping the final line break so that each input still renders
+/// as the lines written in the file.
+///
+/// # Errors
+///
+/// Returns an error string if the file cannot be read or the template is invalid.
+pub fn load_template(path: &Path) -> Result<Template, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read template file {}: {err}", path.display()))?;
+    let text = text.strip_suffix('\n').map_or(text.as_str(), |text| {
+        text.strip_suffix('\r').unwrap_or(text)
+    });
+    parse_template(text)
+}
+
+/// Parses a month given as a number (`1`-`12`) or an English name (`october`, `oct`).
+///
+/// # Errors
+///
+/// Returns an error string if the input is neither a month number nor a month name.
+pub fn parse_month(s: &str) -> Result<Month, String> {
+    match s.parse::<u8>() {
+        Ok(number) => Month::try_from(number).map_err(|_| format!("Month must be 1-12: {s}")),
+        Err(_) => s.parse().map_err(|_| format!("Unknown month: {s}")),
+    }
+}
+
 fn try_parse_fixed_offset(s: &str) -> Option<FixedOffset> {
     let (sign, rest) = match s.chars().next()? {
         '+' => (1i32, &s[1..]),
@@ -135,34 +982,470 @@ fn try_parse_fixed_offset(s: &str) -> Option<FixedOffset> {
 ///
 /// # Errors
 ///
-/// Will return an error string if `input` cannot be parsed to a date.
+/// Will return an error string if `input` cannot be parsed to a date, except in Alfred mode where
+/// the error is printed as an item that cannot be actioned.
 pub fn run(
     input: Option<&str>,
     output_mode: &OutputMode,
-    extra_duration_unit: Option<DurationUnit>,
-    epoch_unit: Option<EpochUnit>,
-    input_timezone: Option<TimeZoneSpec>,
-    output_timezone: Option<TimeZoneSpec>,
+    parse_options: &ParseOptions,
+    snap: Option<&Snap>,
+    convert_options: &ConvertOptions,
     outputs: Option<&[OutputFormat]>,
 ) -> Result<(), &'static str> {
-    let outputs = outputs.unwrap_or(DEFAULT_OUTPUTS);
-    let parsed_input = parsing::parse_input(input, epoch_unit, input_timezone)?;
-    let conversion_results = converting::convert(
-        &parsed_input,
+    let evaluation = evaluate(
+        input,
         &Utc::now(),
-        outputs,
-        output_timezone,
-        extra_duration_unit,
+        parse_options,
+        snap,
+        convert_options,
+        &outputs_for(output_mode, outputs),
     );
+    let Evaluation {
+        instant,
+        display_tz,
+        conversion_results,
+    } = match (output_mode, evaluation) {
+        (OutputMode::Alfred, Err(err)) => {
+            println!("{}", alfred::output_error(err, input.unwrap_or_default()));
+            return Ok(());
+        }
+        (_, evaluation) => evaluation?,
+    };
 
     match output_mode {
         OutputMode::ValuePerLine => output_value_per_line(&conversion_results),
-        OutputMode::Alfred => println!("{}", crate::alfred::output_json(&conversion_results)),
+        OutputMode::Labelled => {
+            for conversion_result in &conversion_results {
+                println!(
+                    "{}: {}",
+                    conversion_result.format.label(),
+                    conversion_result.converted_text
+                );
+            }
+        }
+        OutputMode::Table => {
+            let rows: Vec<_> = conversion_results
+                .iter()
+                .map(|result| vec![result.format.label(), result.converted_text.clone()])
+                .collect();
+            for line in align_columns(&rows) {
+                println!("{line}");
+            }
+        }
+        OutputMode::Alfred => println!("{}", alfred::output_json(&conversion_results)),
+        OutputMode::Shell { shell, prefix } => {
+            for line in crate::shell::output(&conversion_results, *shell, prefix) {
+                println!("{line}");
+            }
+        }
+        OutputMode::Template(template) => println!(
+            "{}",
+            templating::render(
+                template,
+                &conversion_results,
+                input.unwrap_or("now"),
+                &zone_name(display_tz, &instant),
+            )
+        ),
+        OutputMode::Ndjson | OutputMode::Yaml | OutputMode::Toml => print_record(
+            output_mode,
+            &Record::converted(input.unwrap_or("now"), &instant, &conversion_results),
+        ),
     }
 
     Ok(())
 }
 
+/// Converts every line of `inputs` in turn, skipping blank lines. Record modes stream a record per
+/// input with any error embedded in it; otherwise each input is printed as a tab-separated row or
+/// its template, and errors are reported on stderr without stopping the batch.
+///
+/// # Errors
+///
+/// Will return an error string if the inputs cannot be read, or the output mode needs every row
+/// before it can print (labels, tables, Alfred and shell output).
+pub fn run_batch(
+    inputs: impl BufRead,
+    output_mode: &OutputMode,
+    parse_options: &ParseOptions,
+    snap: Option<&Snap>,
+    convert_options: &ConvertOptions,
+    outputs: Option<&[OutputFormat]>,
+) -> Result<(), &'static str> {
+    if matches!(
+        output_mode,
+        OutputMode::Labelled | OutputMode::Table | OutputMode::Alfred | OutputMode::Shell { .. }
+    ) {
+        return Err("Batch output must be value-per-line, --template or --records");
+    }
+    let outputs = outputs_for(output_mode, outputs);
+    for line in inputs.lines() {
+        let line = line.map_err(|_| "Could not read the inputs")?;
+        let input = line.trim();
+        if input.is_empty() {
+            continue;
+        }
+        let evaluation = evaluate(
+            Some(input),
+            &Utc::now(),
+            parse_options,
+            snap,
+            convert_options,
+            &outputs,
+        );
+        match (output_mode, evaluation) {
+            (OutputMode::Ndjson | OutputMode::Yaml | OutputMode::Toml, Ok(evaluation)) => {
+                print_record(
+                    output_mode,
+                    &Record::converted(input, &evaluation.instant, &evaluation.conversion_results),
+                );
+            }
+            (OutputMode::Ndjson | OutputMode::Yaml | OutputMode::Toml, Err(err)) => {
+                print_record(output_mode, &Record::failed(input, err));
+            }
+            (OutputMode::Template(template), Ok(evaluation)) => println!(
+                "{}",
+                templating::render(
+                    template,
+                    &evaluation.conversion_results,
+                    input,
+                    &zone_name(evaluation.display_tz, &evaluation.instant),
+                )
+            ),
+            (_, Ok(evaluation)) => {
+                let values: Vec<_> = evaluation
+                    .conversion_results
+                    .iter()
+                    .map(|r| r.converted_text.as_str())
+                    .collect();
+                println!("{}", values.join("\t"));
+            }
+            (_, Err(err)) => eprintln!("{input}: {err}"),
+        }
+    }
+    Ok(())
+}
+
+/// Starts an interactive prompt that converts one input per line, keeping the timezones, outputs
+/// and reference time as session state that `:` commands can change.
+///
+/// # Errors
+///
+/// Will return an error string if the terminal cannot be used for line editing.
+pub fn run_repl(
+    parse_options: &ParseOptions,
+    snap: Option<&Snap>,
+    convert_options: &ConvertOptions,
+    outputs: Option<&[OutputFormat]>,
+    zone_aliases: &BTreeMap<String, String>,
+) -> Result<(), &'static str> {
+    repl::run(repl::Session::new(
+        parse_options.clone(),
+        snap.copied(),
+        convert_options.clone(),
+        outputs.unwrap_or(DEFAULT_OUTPUTS).to_vec(),
+        zone_aliases.clone(),
+    ))
+}
+
+/// Re-renders the outputs every `interval` until interrupted. Without an input they describe the
+/// current instant, like a clock; with one, the input is parsed once and only durations change,
+/// counting down to it or up from it. On a terminal the lines are rewritten in place, otherwise
+/// each refresh is printed as a tab-separated line.
+///
+/// # Errors
+///
+/// Will return an error string if `input` cannot be parsed to a date.
+pub fn run_watch(
+    input: Option<&str>,
+    interval: std::time::Duration,
+    parse_options: &ParseOptions,
+    snap: Option<&Snap>,
+    convert_options: &ConvertOptions,
+    outputs: Option<&[OutputFormat]>,
+) -> Result<(), &'static str> {
+    let fixed = input
+        .map(|input| parse_and_snap(Some(input), &Utc::now(), parse_options, snap))
+        .transpose()?;
+    if let (Some(parsed_input), true) = (fixed, parse_options.explain) {
+        eprintln!(
+            "{}",
+            parsing::describe_anchor(parsed_input.anchor, parse_options.input_timezone)
+        );
+    }
+    let outputs = watch_outputs(outputs, fixed.is_some());
+    watching::watch(interval, |now| {
+        let parsed_input = match fixed {
+            Some(parsed_input) => parsed_input,
+            None => parse_and_snap(None, now, parse_options, snap)?,
+        };
+        let convert_options = resolve_for_input(convert_options, &parsed_input);
+        Ok(
+            converting::convert(&parsed_input.instant, now, outputs, &convert_options)
+                .into_iter()
+                .map(|conversion_result| conversion_result.converted_text)
+                .collect(),
+        )
+    })
+}
+
+// The outputs chosen with `-o`, the config file or a profile, or else a countdown to a fixed
+// input or the clock.
+fn watch_outputs(outputs: Option<&[OutputFormat]>, counting: bool) -> &[OutputFormat] {
+    outputs.unwrap_or(if counting {
+        WATCH_COUNTDOWN_OUTPUTS
+    } else {
+        WATCH_CLOCK_OUTPUTS
+    })
+}
+
+/// Prints a row of conversions for every step of `range`, tab-separated in value-per-line mode.
+/// Both ends are snapped with `snap` before stepping.
+///
+/// # Errors
+///
+/// Will return an error string if either end of the range cannot be parsed, or the step is not a
+/// duration moving from the start towards the end. In Alfred mode these are printed as an item
+/// instead.
+pub fn run_range(
+    range: &RangeSpec,
+    output_mode: &OutputMode,
+    parse_options: &ParseOptions,
+    snap: Option<&Snap>,
+    convert_options: &ConvertOptions,
+    outputs: Option<&[OutputFormat]>,
+) -> Result<(), &'static str> {
+    match output_range(
+        range,
+        output_mode,
+        parse_options,
+        snap,
+        convert_options,
+        outputs,
+    ) {
+        Err(err) if matches!(output_mode, OutputMode::Alfred) => {
+            let input = format!("{} to {} every {}", range.start, range.end, range.step);
+            println!("{}", alfred::output_error(err, &input));
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+// Every error is returned before anything is printed, so Alfred can show it as an item instead.
+fn output_range(
+    range: &RangeSpec,
+    output_mode: &OutputMode,
+    parse_options: &ParseOptions,
+    snap: Option<&Snap>,
+    convert_options: &ConvertOptions,
+    outputs: Option<&[OutputFormat]>,
+) -> Result<(), &'static str> {
+    let outputs = outputs_for(output_mode, outputs);
+    let now = Utc::now();
+    let start = parse_and_snap(Some(range.start), &now, parse_options, snap)?;
+    let end = parse_and_snap(Some(range.end), &now, parse_options, snap)?;
+    if parse_options.explain {
+        for (label, parsed) in [("Start", start), ("End", end)] {
+            eprintln!(
+                "{label}: {}",
+                parsing::describe_anchor(parsed.anchor, parse_options.input_timezone)
+            );
+        }
+    }
+    let step = duration::parse_calendar_duration(range.step)
+        .ok_or("--step must be a duration such as 1h, 1 month or PT15M")?;
+    let convert_options = resolve_for_input(convert_options, &start);
+    let rows = ranging::instants(start.instant, end.instant, step, range.timezone)?
+        .map(|instant| converting::convert(&instant, &now, &outputs, &convert_options));
+
+    match output_mode {
+        OutputMode::ValuePerLine => {
+            for row in rows {
+                let values: Vec<_> = row.iter().map(|r| r.converted_text.as_str()).collect();
+                println!("{}", values.join("\t"));
+            }
+        }
+        OutputMode::Labelled | OutputMode::Table => {
+            let mut rows = rows.peekable();
+            let header = rows
+                .peek()
+                .map(|row| row.iter().map(|r| r.format.label()).collect())
+                .unwrap_or_default();
+            let cells = iter::once(header)
+                .chain(rows.map(|row| row.into_iter().map(|r| r.converted_text).collect()));
+            if matches!(output_mode, OutputMode::Table) {
+                let cells: Vec<_> = cells.collect();
+                for line in align_columns(&cells) {
+                    println!("{line}");
+                }
+            } else {
+                for row in cells {
+                    println!("{}", row.join("\t"));
+                }
+            }
+        }
+        OutputMode::Alfred => {
+            let rows: Vec<_> = rows.collect();
+            println!("{}", alfred::output_json_rows(&rows));
+        }
+        OutputMode::Shell { shell, prefix } => {
+            let rows: Vec<_> = rows.collect();
+            for line in crate::shell::output_rows(&rows, *shell, prefix) {
+                println!("{line}");
+            }
+        }
+        OutputMode::Template(template) => {
+            let instants = ranging::instants(start.instant, end.instant, step, range.timezone)?;
+            for (instant, row) in instants.zip(rows) {
+                let input = instant.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+                let zone = zone_name(convert_options.display_tz, &instant);
+                println!("{}", templating::render(template, &row, &input, &zone));
+            }
+        }
+        OutputMode::Ndjson | OutputMode::Yaml | OutputMode::Toml => {
+            let instants = ranging::instants(start.instant, end.instant, step, range.timezone)?;
+            for (instant, row) in instants.zip(rows) {
+                let input = instant.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+                print_record(output_mode, &Record::converted(&input, &instant, &row));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Parses, snaps and converts a single input, returning the instant it resolved to alongside the
+// conversions.
+fn evaluate(
+    input: Option<&str>,
+    now: &DateTime<Utc>,
+    parse_options: &ParseOptions,
+    snap: Option<&Snap>,
+    convert_options: &ConvertOptions,
+    outputs: &[OutputFormat],
+) -> Result<Evaluation, &'static str> {
+    let parsed_input = parse_and_snap(input, now, parse_options, snap)?;
+    if parse_options.explain {
+        eprintln!(
+            "{}",
+            parsing::describe_anchor(parsed_input.anchor, parse_options.input_timezone)
+        );
+    }
+    let convert_options = resolve_for_input(convert_options, &parsed_input);
+    let conversion_results =
+        converting::convert(&parsed_input.instant, now, outputs, &convert_options);
+    Ok(Evaluation {
+        instant: parsed_input.instant,
+        display_tz: convert_options.display_tz,
+        conversion_results,
+    })
+}
+
+// An input as evaluated for output: the instant it resolved to, the zone its zoned outputs were
+// rendered in (system local when `None`) and the conversions.
+struct Evaluation {
+    instant: DateTime<Utc>,
+    display_tz: Option<TimeZoneSpec>,
+    conversion_results: Vec<ConversionResult>,
+}
+
+// Names the zone placeholder of a template: the timezone, or the system zone as `Local` finds it
+// (`TZ` first). When the system zone has no name, the offset in effect at `instant` stands in.
+fn zone_name(display_tz: Option<TimeZoneSpec>, instant: &DateTime<Utc>) -> String {
+    if let Some(zone) = display_tz {
+        return zone.to_string();
+    }
+    env::var("TZ")
+        .ok()
+        .map(|tz| tz.trim_start_matches(':').to_string())
+        .filter(|tz| !tz.is_empty())
+        .or_else(|| iana_time_zone::get_timezone().ok())
+        .unwrap_or_else(|| instant.with_timezone(&Local).offset().to_string())
+}
+
+// TOML records are followed by a blank line to keep the tables of consecutive inputs apart.
+fn print_record(output_mode: &OutputMode, record: &Record) {
+    match output_mode {
+        OutputMode::Yaml => print!("{}", records::to_yaml(record)),
+        OutputMode::Toml => println!("{}", records::to_toml(record)),
+        _ => println!("{}", records::to_ndjson(record)),
+    }
+}
+
+// Template modes convert exactly the outputs the template refers to.
+fn outputs_for<'a>(
+    output_mode: &OutputMode,
+    outputs: Option<&'a [OutputFormat]>,
+) -> Cow<'a, [OutputFormat]> {
+    match output_mode {
+        OutputMode::Template(template) => Cow::Owned(template.outputs()),
+        _ => Cow::Borrowed(outputs.unwrap_or(DEFAULT_OUTPUTS)),
+    }
+}
+
+fn parse_and_snap(
+    input: Option<&str>,
+    now: &DateTime<Utc>,
+    parse_options: &ParseOptions,
+    snap: Option<&Snap>,
+) -> Result<ParsedInput, &'static str> {
+    let mut parsed_input = parsing::parse_input(input, now, parse_options)?;
+    if let Some(snap) = snap {
+        parsed_input.instant =
+            snapping::snap(&parsed_input.instant, snap).ok_or("Snapped date is out of range")?;
+    }
+    Ok(parsed_input)
+}
+
+// Resolves `Precision::Auto` against the precision the input was written with, displays in a zone
+// named by the input when no output timezone was given, and keeps the input's offset for the
+// original output.
+fn resolve_for_input(
+    convert_options: &ConvertOptions,
+    parsed_input: &ParsedInput,
+) -> ConvertOptions {
+    ConvertOptions {
+        display_tz: convert_options.display_tz.or(parsed_input.zone),
+        original_offset: Some(parsed_input.offset),
+        precision: convert_options
+            .precision
+            .map(|precision| precision.resolve(parsed_input.precision)),
+        ..convert_options.clone()
+    }
+}
+
+// Pads every column but the last to the width of its widest cell, separating columns by two
+// spaces.
+fn align_columns(rows: &[Vec<String>]) -> Vec<String> {
+    let mut widths: Vec<usize> = Vec::new();
+    for row in rows {
+        for (column, cell) in row.iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(column) {
+                Some(widest) => *widest = (*widest).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            let last = row.len().saturating_sub(1);
+            row.iter()
+                .enumerate()
+                .map(|(column, cell)| {
+                    if column == last {
+                        cell.clone()
+                    } else {
+                        format!("{cell:<width$}", width = widths[column])
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+        })
+        .collect()
+}
+
 fn output_value_per_line(conversion_results: &[ConversionResult]) {
     for conversion_result in conversion_results {
         println!("{}", conversion_result.converted_text);
@@ -233,4 +1516,272 @@ mod tests {
     fn parse_timezone_spec_invalid() {
         assert!(parse_timezone_spec("Nope/Nowhere").is_err());
     }
+
+    #[test]
+    fn parse_output_format_named() {
+        assert_eq!(parse_output_format("micros"), Ok(OutputFormat::Micros));
+    }
+
+    #[test]
+    fn parse_output_format_epoch_with_decimals() {
+        assert_eq!(
+            parse_output_format("seconds:3"),
+            Ok(OutputFormat::Epoch(EpochUnit::Seconds, 3))
+        );
+        assert_eq!(
+            parse_output_format("us:0"),
+            Ok(OutputFormat::Epoch(EpochUnit::Micros, 0))
+        );
+    }
+
+    #[test]
+    fn parse_output_format_strftime() {
+        assert_eq!(
+            parse_output_format("%F %T"),
+            Ok(OutputFormat::Strftime(String::from("%F %T")))
+        );
+        assert!(parse_output_format("%Q").is_err());
+    }
+
+    #[test]
+    fn parse_output_format_invalid() {
+        assert!(parse_output_format("fortnights").is_err());
+        assert!(parse_output_format("seconds:10").is_err());
+        assert!(parse_output_format("days:3").is_err());
+    }
+
+    #[test]
+    fn parse_input_format_requires_strftime_pattern() {
+        assert_eq!(parse_input_format("%d/%m/%Y"), Ok(String::from("%d/%m/%Y")));
+        assert!(parse_input_format("%Q").is_err());
+        assert!(parse_input_format("dd/mm/yyyy").is_err());
+    }
+
+    #[test]
+    fn input_zone_is_displayed_unless_output_timezone_given() {
+        let tokyo = parse_timezone_spec("Asia/Tokyo").unwrap();
+        let paris = parse_timezone_spec("Europe/Paris").unwrap();
+        let parsed = ParsedInput {
+            instant: Utc::now(),
+            precision: Precision::Millis,
+            anchor: None,
+            zone: Some(paris),
+            offset: FixedOffset::east_opt(3600).unwrap(),
+        };
+        assert_eq!(
+            resolve_for_input(&ConvertOptions::default(), &parsed).display_tz,
+            Some(paris)
+        );
+        let options = ConvertOptions {
+            display_tz: Some(tokyo),
+            ..ConvertOptions::default()
+        };
+        assert_eq!(resolve_for_input(&options, &parsed).display_tz, Some(tokyo));
+    }
+
+    #[test]
+    fn parse_config_settings_and_zones() {
+        let config = parse_config(
+            "output-timezone = \"pdx\"\n\
+             outputs = [\"utc\", \"millis\"]\n\
+             end-of-day = true\n\
+             \n\
+             [zones]\n\
+             pdx = \"America/Los_Angeles\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.output_timezone.as_deref(), Some("pdx"));
+        assert_eq!(
+            config.outputs,
+            Some(vec![String::from("utc"), String::from("millis")])
+        );
+        assert_eq!(config.end_of_day, Some(true));
+        assert_eq!(
+            resolve_timezone("pdx", &config.zones).map(|zone| zone.to_string()),
+            Ok(String::from("America/Los_Angeles"))
+        );
+        assert_eq!(
+            resolve_timezone("+05:30", &config.zones).map(|zone| zone.to_string()),
+            Ok(String::from("+05:30"))
+        );
+        assert!(parse_config("output_timezone = \"UTC\"").is_err());
+    }
+
+    #[test]
+    fn watch_without_outputs_uses_its_defaults() {
+        let config = parse_config("").unwrap();
+        assert_eq!(config.fallback_profile(false, true).outputs, None);
+        assert_eq!(
+            config
+                .fallback_profile(false, false)
+                .outputs
+                .map(|outputs| outputs.len()),
+            Some(DEFAULT_OUTPUTS.len())
+        );
+        assert_eq!(
+            config
+                .fallback_profile(true, false)
+                .outputs
+                .map(|outputs| outputs.len()),
+            Some(ALFRED_OUTPUTS.len())
+        );
+        assert_eq!(watch_outputs(None, true), WATCH_COUNTDOWN_OUTPUTS);
+        assert_eq!(watch_outputs(None, false), WATCH_CLOCK_OUTPUTS);
+        assert_eq!(
+            watch_outputs(Some(&[OutputFormat::Millis]), true),
+            [OutputFormat::Millis]
+        );
+
+        let config = parse_config("[profiles.default]\noutputs = [\"millis\"]\n").unwrap();
+        assert_eq!(
+            config.fallback_profile(false, true).outputs,
+            Some(vec![String::from("millis")])
+        );
+    }
+
+    #[test]
+    fn profiles_from_config_and_built_in() {
+        let config = parse_config(
+            "[profiles.sql]\n\
+             outputs = [\"%F %T\"]\n\
+             output-timezone = \"UTC\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.profile("sql"),
+            Ok(Profile {
+                outputs: Some(vec![String::from("%F %T")]),
+                output_timezone: Some(String::from("UTC")),
+                ..Profile::default()
+            })
+        );
+        assert_eq!(
+            config.profile(DEFAULT_PROFILE).unwrap().outputs,
+            Some(vec![
+                String::from("utc"),
+                String::from("zoned"),
+                String::from("millis"),
+                String::from("duration"),
+            ])
+        );
+        assert!(
+            config
+                .profile("all")
+                .unwrap()
+                .outputs
+                .is_some_and(|outputs| outputs.contains(&String::from("day-of-year")))
+        );
+        assert_eq!(
+            config.profile("logs"),
+            Err(String::from("Unknown profile: logs"))
+        );
+    }
+
+    #[test]
+    fn align_columns_pads_all_but_last() {
+        let rows = vec![
+            vec![
+                String::from("RFC3339 - UTC"),
+                String::from("2019-11-30T21:23:40.890Z"),
+            ],
+            vec![String::from("Epoch Millis"), String::from("1575149020890")],
+        ];
+        assert_eq!(
+            align_columns(&rows),
+            vec![
+                "RFC3339 - UTC  2019-11-30T21:23:40.890Z",
+                "Epoch Millis   1575149020890",
+            ]
+        );
+    }
+
+    #[test]
+    fn output_format_names() {
+        assert_eq!(OutputFormat::FuzzyDuration.to_string(), "fuzzy-duration");
+        assert_eq!(
+            OutputFormat::Epoch(EpochUnit::Seconds, 3).to_string(),
+            "seconds:3"
+        );
+    }
+
+    #[test]
+    fn parse_month_number_and_name() {
+        assert_eq!(parse_month("10"), Ok(Month::October));
+        assert_eq!(parse_month("Oct"), Ok(Month::October));
+        assert_eq!(parse_month("april"), Ok(Month::April));
+        assert_eq!(
+            parse_month("13"),
+            Err(String::from("Month must be 1-12: 13"))
+        );
+        assert_eq!(
+            parse_month("Fall"),
+            Err(String::from("Unknown month: Fall"))
+        );
+    }
+
+    #[test]
+    fn parse_duration_precision_components() {
+        assert_eq!(
+            parse_duration_precision("2"),
+            Ok(DurationPrecision::Components(2))
+        );
+    }
+
+    #[test]
+    fn parse_duration_precision_unit() {
+        assert_eq!(
+            parse_duration_precision("Minutes"),
+            Ok(DurationPrecision::Minutes)
+        );
+    }
+
+    #[test]
+    fn parse_duration_precision_invalid() {
+        assert!(parse_duration_precision("0").is_err());
+        assert!(parse_duration_precision("fortnights").is_err());
+    }
+
+    #[test]
+    fn output_format_keys_round_trip() {
+        let formats = OutputFormat::value_variants().iter().cloned().chain([
+            OutputFormat::DurationSinceUnits(DurationUnit::Milliseconds),
+            OutputFormat::Epoch(EpochUnit::Micros, 9),
+        ]);
+        for format in formats {
+            assert_eq!(OutputFormat::from_key(&format.key()), Some(format));
+        }
+        assert_eq!(OutputFormat::from_key("epoch_millis_10"), None);
+        assert_eq!(OutputFormat::from_key("fuzzy-duration"), None);
+    }
+
+    #[test]
+    fn parse_template_placeholders() {
+        let template = parse_template("{fuzzy-duration} {millis:3} {{%F}}").unwrap();
+        assert_eq!(
+            template.outputs(),
+            vec![
+                OutputFormat::FuzzyDuration,
+                OutputFormat::Epoch(EpochUnit::Millis, 3),
+                OutputFormat::Strftime(String::from("%F"))
+            ]
+        );
+        assert_eq!(
+            parse_template("{utc} {fortnights}"),
+            Err(String::from(
+                "Unknown placeholder in template: {fortnights}"
+            ))
+        );
+        assert_eq!(
+            parse_template("{{utc}"),
+            Err(String::from("Unclosed placeholder in template: {{utc}"))
+        );
+    }
+
+    #[test]
+    fn parse_shell_prefix_identifiers() {
+        assert_eq!(parse_shell_prefix("TT_"), Ok(String::from("TT_")));
+        assert_eq!(parse_shell_prefix(""), Ok(String::new()));
+        assert!(parse_shell_prefix("1TT").is_err());
+        assert!(parse_shell_prefix("TT-").is_err());
+    }
 }
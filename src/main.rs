@@ -1,75 +1,934 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use std::io;
+use std::path::PathBuf;
 use std::process;
+use std::time::Duration;
 
-use clap::Parser;
+use chrono::{Month, NaiveDate, Weekday};
+use clap::builder::BoolishValueParser;
+use clap::{Parser, Subcommand, ValueEnum};
+use timeturner::BusinessCalendar;
+use timeturner::ByteOrder;
+use timeturner::CalendarUnit;
+use timeturner::Config;
+use timeturner::ConvertOptions;
+use timeturner::DEFAULT_SHELL_PREFIX;
+use timeturner::DurationPrecision;
 use timeturner::DurationUnit;
 use timeturner::EpochUnit;
+use timeturner::ExcelSystem;
 use timeturner::OutputFormat;
 use timeturner::OutputMode;
+use timeturner::ParseOptions;
+use timeturner::Precision;
+use timeturner::Profile;
+use timeturner::RangeSpec;
+use timeturner::RecordFormat;
+use timeturner::Rounding;
+use timeturner::Shell;
+use timeturner::Snap;
+use timeturner::Template;
 use timeturner::TimeZoneSpec;
+use timeturner::WeekStart;
 
 #[derive(Debug, Parser)]
 #[command(name = "timeturner", about = "Manipulate date-time strings", version)]
 struct Opt {
-    #[arg(long, help = "Output in JSON for Alfred Workflow integration")]
-    alfred: bool,
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_CONFIG",
+        help = "Config file to read defaults from (default: $XDG_CONFIG_HOME/timeturner/config.toml)"
+    )]
+    config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Print the settings in effect after merging the config file, environment and flags, then exit"
+    )]
+    print_config: bool,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_PROFILE",
+        help = "Named set of outputs, timezones and precision from the config file, or the built-in default or all"
+    )]
+    profile: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_ALFRED",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new(),
+        help = "Output in JSON for Alfred Workflow integration"
+    )]
+    alfred: Option<bool>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_LABELS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new(),
+        help = "Prefix each output with its label, e.g. `Epoch Millis: 1575149020890`"
+    )]
+    labels: Option<bool>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_TABLE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new(),
+        help = "Print outputs as an aligned table of labels and values"
+    )]
+    table: Option<bool>,
 
-    #[arg(short, long)]
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_SHELL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "bash",
+        help = "Output variable assignments to eval in a shell (bash, zsh, fish or powershell; defaults to bash)"
+    )]
+    shell: Option<Shell>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_PREFIX",
+        value_parser = timeturner::parse_shell_prefix,
+        help = "Prefix of the variable names set by --shell [default: TT_]"
+    )]
+    prefix: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_TEMPLATE",
+        value_parser = timeturner::parse_template,
+        allow_hyphen_values = true,
+        help = "Render each input from a template such as '{utc} ({millis}) {duration}', with outputs named by key plus {input} and {zone}"
+    )]
+    template: Option<Template>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_TEMPLATE_FILE",
+        conflicts_with = "template",
+        help = "Read the --template from a file"
+    )]
+    template_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_RECORDS",
+        help = "Output a structured record of each input (ndjson, yaml or toml); with batch, errors are embedded in the records"
+    )]
+    records: Option<RecordFormat>,
+
+    #[arg(short, long, global = true, env = "TIMETURNER_DURATION_UNIT")]
     duration_unit: Option<DurationUnit>,
 
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_DURATION_PRECISION",
+        value_parser = timeturner::parse_duration_precision,
+        help = "Limit the human-readable duration to a number of components (e.g. 2) or a smallest unit (years, months, days, hours, minutes, seconds, millis)"
+    )]
+    duration_precision: Option<DurationPrecision>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_DURATION_ROUNDING",
+        help = "How to round the part of the duration dropped by --duration-precision (default: floor)"
+    )]
+    duration_rounding: Option<Rounding>,
+
     #[arg(
         short = 'u',
         long,
+        global = true,
+        env = "TIMETURNER_EPOCH_UNIT",
         help = "Force epoch input to be interpreted in the given unit (seconds, millis/ms, micros/us, nanos/ns)"
     )]
     epoch_unit: Option<EpochUnit>,
 
     #[arg(
         long,
+        global = true,
+        env = "TIMETURNER_BYTE_ORDER",
+        help = "Decode the input as a raw 4 or 8 byte hex dump in the given byte order (be, le)"
+    )]
+    byte_order: Option<ByteOrder>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_EXCEL",
+        help = "Read the input as an Excel serial date in the 1900 (Windows) or 1904 (old Mac) date system"
+    )]
+    excel: Option<ExcelSystem>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_INPUT_FORMAT",
+        value_parser = timeturner::parse_input_format,
+        help = "strftime pattern to try before the built-in input formats, e.g. \"%d/%m/%Y %H:%M\"; repeat to try several in order"
+    )]
+    input_format: Option<Vec<String>>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_INPUT_TIMEZONE",
         allow_hyphen_values = true,
-        value_parser = timeturner::parse_timezone_spec,
-        help = "Timezone to assume for inputs lacking explicit zone info (IANA name or fixed offset, e.g. America/New_York, -05:00)"
+        help = "Timezone to assume for inputs lacking explicit zone info (IANA name, fixed offset or configured alias, e.g. America/New_York, -05:00)"
     )]
-    input_timezone: Option<TimeZoneSpec>,
+    input_timezone: Option<String>,
 
     #[arg(
         long,
+        global = true,
+        env = "TIMETURNER_OUTPUT_TIMEZONE",
         allow_hyphen_values = true,
-        value_parser = timeturner::parse_timezone_spec,
-        help = "Timezone used for the zoned RFC3339 output (defaults to system local; IANA name or fixed offset)"
+        help = "Timezone used for the zoned RFC3339 output (defaults to system local; IANA name, fixed offset or configured alias)"
     )]
-    output_timezone: Option<TimeZoneSpec>,
+    output_timezone: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_ANCHOR",
+        help = "Date (YYYY-MM-DD) that a bare time such as 14:30 falls on (defaults to today in the input timezone)"
+    )]
+    anchor: Option<NaiveDate>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_END_OF_DAY",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new(),
+        help = "Resolve a bare date such as 2020-03-01 to the last instant of the day instead of midnight"
+    )]
+    end_of_day: Option<bool>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_EXPLAIN",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new(),
+        help = "Print to stderr which anchor, if any, was used to complete a bare time or date"
+    )]
+    explain: Option<bool>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_WEEKEND",
+        value_delimiter = ',',
+        help = "Comma-separated weekdays that are not business days, for +Nbd arithmetic and the business-days output (default: sat,sun)"
+    )]
+    weekend: Option<Vec<Weekday>>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_HOLIDAYS",
+        help = "iCalendar (.ics) or plain text file of holiday dates (one YYYY-MM-DD per line) that are not business days"
+    )]
+    holidays: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_FISCAL_YEAR_START",
+        value_parser = timeturner::parse_month,
+        help = "First month of the fiscal year for the fiscal-year and fiscal-quarter outputs (number or name, default january)"
+    )]
+    fiscal_year_start: Option<Month>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_YEAR",
+        help = "Year for inputs without one, such as syslog (Oct 27 22:03:19) or ls -l (Nov 30 13:23) timestamps (defaults to the most recent past occurrence)"
+    )]
+    year: Option<i32>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_PRECISION",
+        help = "Sub-second precision for RFC3339 outputs, also adding a fractional part to epoch outputs (secs, millis, micros, nanos, auto to match the input)"
+    )]
+    precision: Option<Precision>,
+
+    #[arg(
+        long,
+        global = true,
+        group = "snap",
+        help = "Round the input down to the start of the given unit (second, minute, hour, day, week, month, quarter, year) in the output timezone"
+    )]
+    floor: Option<CalendarUnit>,
+
+    #[arg(
+        long,
+        global = true,
+        group = "snap",
+        help = "Round the input up to the start of the next given unit in the output timezone"
+    )]
+    ceil: Option<CalendarUnit>,
+
+    #[arg(
+        long,
+        global = true,
+        group = "snap",
+        help = "Round the input to the nearest start of the given unit in the output timezone"
+    )]
+    round: Option<CalendarUnit>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "TIMETURNER_WEEK_START",
+        help = "First day of the week for --floor/--ceil/--round week (monday/iso, sunday; default: monday)"
+    )]
+    week_start: Option<WeekStart>,
 
     #[arg(
         short = 'o',
         long,
+        global = true,
+        env = "TIMETURNER_OUTPUTS",
         value_delimiter = ',',
-        help = "Comma-separated list of outputs to produce (default: utc,zoned,millis,duration)"
+        value_parser = timeturner::parse_output_format,
+        help = "Comma-separated list of outputs to produce (default: utc,zoned,millis,duration); an epoch unit with decimal places such as seconds:3 is also accepted"
     )]
     outputs: Option<Vec<OutputFormat>>,
 
+    #[command(subcommand)]
+    command: Option<Command>,
+
     input: Option<String>,
 }
 
+#[derive(Debug, Subcommand)]
+enum Command {
+    #[command(about = "Print a row of outputs for every step from start to end (inclusive)")]
+    Range {
+        start: String,
+
+        end: String,
+
+        #[arg(
+            long,
+            allow_hyphen_values = true,
+            help = "Step between rows as a humantime or ISO 8601 duration (e.g. 1h, 1 month, PT15M, -1h to count down); calendar units follow the output timezone"
+        )]
+        step: String,
+    },
+
+    #[command(about = "Convert every line of stdin, continuing past inputs that cannot be parsed")]
+    Batch,
+
+    #[command(
+        about = "Convert inputs interactively, keeping timezones, outputs and the reference time between lines (see :help)"
+    )]
+    Repl,
+
+    #[command(
+        about = "Refresh the outputs for the current instant, or a countdown to a fixed input, until interrupted"
+    )]
+    Watch {
+        input: Option<String>,
+
+        #[arg(
+            long,
+            default_value_t = 1000,
+            value_parser = clap::value_parser!(u64).range(1..),
+            help = "Milliseconds between refreshes"
+        )]
+        interval: u64,
+    },
+}
+
 fn main() {
-    let opt: Opt = Parser::parse();
-
-    if let Err(err) = timeturner::run(
-        opt.input.as_deref(),
-        &output_mode(&opt),
-        opt.duration_unit,
-        opt.epoch_unit,
-        opt.input_timezone,
-        opt.output_timezone,
-        opt.outputs.as_deref(),
-    ) {
-        eprintln!("{err}");
-        process::exit(1);
+    let mut opt: Opt = Parser::parse();
+
+    let config = merge_settings(&mut opt);
+    let input_timezone = timezone(
+        "--input-timezone",
+        opt.input_timezone.as_deref(),
+        &config.zones,
+    );
+    let output_timezone = timezone(
+        "--output-timezone",
+        opt.output_timezone.as_deref(),
+        &config.zones,
+    );
+    if opt.print_config {
+        print_config(
+            &opt,
+            opt.profile.as_deref(),
+            input_timezone,
+            output_timezone,
+            &config,
+        );
+        return;
+    }
+
+    let business_calendar = business_calendar(&opt);
+    let parse_options = ParseOptions {
+        epoch_unit: opt.epoch_unit,
+        input_timezone,
+        byte_order: opt.byte_order,
+        excel: opt.excel,
+        anchor: opt.anchor,
+        end_of_day: opt.end_of_day.unwrap_or_default(),
+        explain: opt.explain.unwrap_or_default(),
+        business_calendar: business_calendar.clone(),
+        year: opt.year,
+        input_formats: opt.input_format.clone().unwrap_or_default(),
+    };
+    let convert_options = ConvertOptions {
+        display_tz: output_timezone,
+        extra_duration_unit: opt.duration_unit,
+        duration_precision: opt.duration_precision.unwrap_or_default(),
+        duration_rounding: opt.duration_rounding.unwrap_or_default(),
+        precision: opt.precision,
+        business_calendar,
+        fiscal_year_start: opt.fiscal_year_start,
+        original_offset: None,
+    };
+    let calendar_timezone = output_timezone.or(input_timezone);
+
+    let result = match &opt.command {
+        Some(Command::Range { start, end, step }) => timeturner::run_range(
+            &RangeSpec {
+                start,
+                end,
+                step,
+                timezone: calendar_timezone,
+            },
+            &output_mode(&opt),
+            &parse_options,
+            snap(&opt, calendar_timezone).as_ref(),
+            &convert_options,
+            opt.outputs.as_deref(),
+        ),
+        Some(Command::Batch) => timeturner::run_batch(
+            io::stdin().lock(),
+            &output_mode(&opt),
+            &parse_options,
+            snap(&opt, calendar_timezone).as_ref(),
+            &convert_options,
+            opt.outputs.as_deref(),
+        ),
+        Some(Command::Repl) => timeturner::run_repl(
+            &parse_options,
+            snap(&opt, calendar_timezone).as_ref(),
+            &convert_options,
+            opt.outputs.as_deref(),
+            &config.zones,
+        ),
+        Some(Command::Watch { input, interval }) => timeturner::run_watch(
+            input.as_deref(),
+            Duration::from_millis(*interval),
+            &parse_options,
+            snap(&opt, calendar_timezone).as_ref(),
+            &convert_options,
+            opt.outputs.as_deref(),
+        ),
+        None => timeturner::run(
+            opt.input.as_deref(),
+            &output_mode(&opt),
+            &parse_options,
+            snap(&opt, calendar_timezone).as_ref(),
+            &convert_options,
+            opt.outputs.as_deref(),
+        ),
+    };
+
+    if let Err(err) = result {
+        exit_with_error(err);
+    }
+}
+
+fn business_calendar(opt: &Opt) -> BusinessCalendar {
+    let holidays = match opt.holidays.as_deref().map(timeturner::load_holidays) {
+        Some(Ok(holidays)) => holidays,
+        Some(Err(err)) => exit_with_error(&err),
+        None => BTreeSet::new(),
+    };
+    BusinessCalendar {
+        weekend: opt
+            .weekend
+            .clone()
+            .unwrap_or_else(|| BusinessCalendar::default().weekend),
+        holidays,
     }
 }
 
+// Fills the settings not given as flags or environment variables from the selected profile, then
+// the config file, then the default profile, and returns the config file.
+fn merge_settings(opt: &mut Opt) -> Config {
+    let config_path = opt
+        .config
+        .clone()
+        .or_else(|| timeturner::default_config_path().filter(|path| path.exists()));
+    let config = config_path
+        .as_deref()
+        .map(timeturner::load_config)
+        .transpose()
+        .unwrap_or_else(|err| exit_with_error(&err))
+        .unwrap_or_default();
+    let lookup = |name: &str| {
+        config
+            .profile(name)
+            .unwrap_or_else(|err| exit_with_error(&err))
+    };
+    apply_snap_env(opt);
+    opt.profile = opt.profile.take().or_else(|| config.profile.clone());
+    if let Some(name) = opt.profile.clone() {
+        apply_profile(opt, &name, &lookup(&name));
+    }
+    apply_config(opt, &config);
+    let alfred = opt.alfred.unwrap_or_default();
+    let watching = matches!(opt.command, Some(Command::Watch { .. }));
+    apply_profile(
+        opt,
+        if alfred {
+            timeturner::ALFRED_PROFILE
+        } else {
+            timeturner::DEFAULT_PROFILE
+        },
+        &config.fallback_profile(alfred, watching),
+    );
+    config
+}
+
+fn exit_with_error(err: &str) -> ! {
+    eprintln!("{err}");
+    process::exit(1);
+}
+
+// Fills the settings a profile bundles, where not already given.
+fn apply_profile(opt: &mut Opt, name: &str, profile: &Profile) {
+    let invalid = |key: &str, err: String| format!("Invalid {key} in profile {name}: {err}");
+    opt.input_timezone = opt
+        .input_timezone
+        .take()
+        .or_else(|| profile.input_timezone.clone());
+    opt.output_timezone = opt
+        .output_timezone
+        .take()
+        .or_else(|| profile.output_timezone.clone());
+    opt.precision = opt.precision.or_else(|| {
+        profile.precision.as_deref().map(|precision| {
+            value_enum(precision).unwrap_or_else(|err| exit_with_error(&invalid("precision", err)))
+        })
+    });
+    opt.outputs = opt.outputs.take().or_else(|| {
+        profile.outputs.as_ref().map(|outputs| {
+            outputs
+                .iter()
+                .map(|output| {
+                    timeturner::parse_output_format(output)
+                        .unwrap_or_else(|err| exit_with_error(&invalid("outputs", err)))
+                })
+                .collect()
+        })
+    });
+}
+
+// The snapping flags are alternatives, so their environment variables are read here rather than
+// by clap: a flag on the command line replaces all of them instead of conflicting with one.
+fn apply_snap_env(opt: &mut Opt) {
+    if opt.floor.is_some() || opt.ceil.is_some() || opt.round.is_some() {
+        return;
+    }
+    let from_env = |name: &str| {
+        std::env::var(name).ok().map(|value| {
+            value_enum(&value)
+                .unwrap_or_else(|err| exit_with_error(&format!("Invalid {name}: {err}")))
+        })
+    };
+    opt.floor = from_env("TIMETURNER_FLOOR");
+    opt.ceil = from_env("TIMETURNER_CEIL");
+    opt.round = from_env("TIMETURNER_ROUND");
+    if [opt.floor, opt.ceil, opt.round].iter().flatten().count() > 1 {
+        exit_with_error(
+            "Only one of TIMETURNER_FLOOR, TIMETURNER_CEIL and TIMETURNER_ROUND can be set",
+        );
+    }
+}
+
+// Fills every setting not given as a flag or environment variable from the config file.
+fn apply_config(opt: &mut Opt, config: &Config) {
+    opt.alfred = opt.alfred.or(config.alfred);
+    opt.labels = opt.labels.or(config.labels);
+    opt.table = opt.table.or(config.table);
+    opt.shell = opt
+        .shell
+        .or_else(|| configured("shell", config.shell.as_deref(), value_enum));
+    opt.records = opt
+        .records
+        .or_else(|| configured("records", config.records.as_deref(), value_enum));
+    opt.prefix = opt.prefix.take().or_else(|| {
+        configured(
+            "prefix",
+            config.prefix.as_deref(),
+            timeturner::parse_shell_prefix,
+        )
+    });
+    if opt.template.is_none() && opt.template_file.is_none() {
+        opt.template = configured(
+            "template",
+            config.template.as_deref(),
+            timeturner::parse_template,
+        );
+        opt.template_file.clone_from(&config.template_file);
+    }
+    opt.duration_unit = opt
+        .duration_unit
+        .or_else(|| configured("duration-unit", config.duration_unit.as_deref(), value_enum));
+    opt.duration_precision = opt.duration_precision.or_else(|| {
+        configured(
+            "duration-precision",
+            config.duration_precision.as_deref(),
+            timeturner::parse_duration_precision,
+        )
+    });
+    opt.duration_rounding = opt.duration_rounding.or_else(|| {
+        configured(
+            "duration-rounding",
+            config.duration_rounding.as_deref(),
+            value_enum,
+        )
+    });
+    apply_input_config(opt, config);
+    opt.input_timezone = opt
+        .input_timezone
+        .take()
+        .or_else(|| config.input_timezone.clone());
+    opt.output_timezone = opt
+        .output_timezone
+        .take()
+        .or_else(|| config.output_timezone.clone());
+    opt.anchor = opt.anchor.or_else(|| {
+        configured("anchor", config.anchor.as_deref(), |s| {
+            s.parse().map_err(|_| format!("Invalid date: {s}"))
+        })
+    });
+    opt.end_of_day = opt.end_of_day.or(config.end_of_day);
+    opt.explain = opt.explain.or(config.explain);
+    opt.weekend = opt.weekend.take().or_else(|| {
+        configured_list("weekend", config.weekend.as_deref(), |s| {
+            s.parse().map_err(|_| format!("Unknown weekday: {s}"))
+        })
+    });
+    opt.holidays = opt.holidays.take().or_else(|| config.holidays.clone());
+    opt.fiscal_year_start = opt.fiscal_year_start.or_else(|| {
+        configured(
+            "fiscal-year-start",
+            config.fiscal_year_start.as_deref(),
+            timeturner::parse_month,
+        )
+    });
+    opt.year = opt.year.or(config.year);
+    opt.precision = opt
+        .precision
+        .or_else(|| configured("precision", config.precision.as_deref(), value_enum));
+    // The snapping flags are alternatives, so one given on the command line replaces all of
+    // the configured ones
+    if opt.floor.is_none() && opt.ceil.is_none() && opt.round.is_none() {
+        opt.floor = configured("floor", config.floor.as_deref(), value_enum);
+        opt.ceil = configured("ceil", config.ceil.as_deref(), value_enum);
+        opt.round = configured("round", config.round.as_deref(), value_enum);
+    }
+    opt.week_start = opt
+        .week_start
+        .or_else(|| configured("week-start", config.week_start.as_deref(), value_enum));
+    opt.outputs = opt.outputs.take().or_else(|| {
+        configured_list(
+            "outputs",
+            config.outputs.as_deref(),
+            timeturner::parse_output_format,
+        )
+    });
+}
+
+// Fills the settings that choose how the input is read from the config file.
+fn apply_input_config(opt: &mut Opt, config: &Config) {
+    opt.epoch_unit = opt
+        .epoch_unit
+        .or_else(|| configured("epoch-unit", config.epoch_unit.as_deref(), value_enum));
+    opt.byte_order = opt
+        .byte_order
+        .or_else(|| configured("byte-order", config.byte_order.as_deref(), value_enum));
+    opt.excel = opt
+        .excel
+        .or_else(|| configured("excel", config.excel.as_deref(), value_enum));
+    opt.input_format = opt.input_format.take().or_else(|| {
+        configured_list(
+            "input-format",
+            config.input_format.as_deref(),
+            timeturner::parse_input_format,
+        )
+    });
+}
+
+fn configured<T>(
+    key: &str,
+    value: Option<&str>,
+    parse: impl FnOnce(&str) -> Result<T, String>,
+) -> Option<T> {
+    value.map(|value| {
+        parse(value)
+            .unwrap_or_else(|err| exit_with_error(&format!("Invalid {key} in config file: {err}")))
+    })
+}
+
+fn configured_list<T>(
+    key: &str,
+    values: Option<&[String]>,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Option<Vec<T>> {
+    values.map(|values| {
+        values
+            .iter()
+            .filter_map(|value| configured(key, Some(value), &parse))
+            .collect()
+    })
+}
+
+fn value_enum<T: ValueEnum>(s: &str) -> Result<T, String> {
+    T::from_str(s, true)
+}
+
+fn timezone(
+    flag: &str,
+    value: Option<&str>,
+    aliases: &BTreeMap<String, String>,
+) -> Option<TimeZoneSpec> {
+    value.map(|value| {
+        timeturner::resolve_timezone(value, aliases)
+            .unwrap_or_else(|err| exit_with_error(&format!("Invalid {flag}: {err}")))
+    })
+}
+
+// Prints the merged settings in config file syntax, leaving out those that are unset.
+fn print_config(
+    opt: &Opt,
+    profile: Option<&str>,
+    input_timezone: Option<TimeZoneSpec>,
+    output_timezone: Option<TimeZoneSpec>,
+    config: &Config,
+) {
+    let settings = [
+        ("profile", profile.map(quoted)),
+        ("alfred", Some(opt.alfred.unwrap_or_default().to_string())),
+        ("labels", Some(opt.labels.unwrap_or_default().to_string())),
+        ("table", Some(opt.table.unwrap_or_default().to_string())),
+        ("records", opt.records.map(|format| named(&format))),
+        ("shell", opt.shell.map(|shell| named(&shell))),
+        (
+            "prefix",
+            Some(quoted(
+                opt.prefix.as_deref().unwrap_or(DEFAULT_SHELL_PREFIX),
+            )),
+        ),
+        ("template", opt.template.as_ref().map(quoted)),
+        (
+            "template-file",
+            opt.template_file
+                .as_ref()
+                .map(|path| quoted(&path.display())),
+        ),
+        ("duration-unit", opt.duration_unit.map(|unit| named(&unit))),
+        (
+            "duration-precision",
+            opt.duration_precision.map(|precision| match precision {
+                DurationPrecision::Components(count) => quoted(&count),
+                unit => quoted(&format!("{unit:?}").to_lowercase()),
+            }),
+        ),
+        (
+            "duration-rounding",
+            Some(named(&opt.duration_rounding.unwrap_or_default())),
+        ),
+        ("epoch-unit", opt.epoch_unit.map(|unit| named(&unit))),
+        ("byte-order", opt.byte_order.map(|order| named(&order))),
+        ("excel", opt.excel.map(|system| named(&system))),
+        (
+            "input-format",
+            opt.input_format
+                .as_ref()
+                .map(|formats| list(formats.iter().map(quoted))),
+        ),
+        ("input-timezone", input_timezone.map(|zone| quoted(&zone))),
+        ("output-timezone", output_timezone.map(|zone| quoted(&zone))),
+        ("anchor", opt.anchor.map(|date| quoted(&date))),
+        (
+            "end-of-day",
+            Some(opt.end_of_day.unwrap_or_default().to_string()),
+        ),
+        ("explain", Some(opt.explain.unwrap_or_default().to_string())),
+        (
+            "weekend",
+            Some(list(
+                opt.weekend
+                    .clone()
+                    .unwrap_or_else(|| BusinessCalendar::default().weekend)
+                    .iter()
+                    .map(|day| quoted(&day.to_string().to_lowercase())),
+            )),
+        ),
+        ("holidays", opt.holidays.as_ref().map(quoted)),
+        (
+            "fiscal-year-start",
+            opt.fiscal_year_start
+                .map(|month| quoted(&month.name().to_lowercase())),
+        ),
+        ("year", opt.year.map(|year| year.to_string())),
+        (
+            "precision",
+            opt.precision.map(|precision| named(&precision)),
+        ),
+        ("floor", opt.floor.map(|unit| named(&unit))),
+        ("ceil", opt.ceil.map(|unit| named(&unit))),
+        ("round", opt.round.map(|unit| named(&unit))),
+        (
+            "week-start",
+            Some(named(&opt.week_start.unwrap_or_default())),
+        ),
+        (
+            "outputs",
+            Some(list(
+                opt.outputs
+                    .as_deref()
+                    .unwrap_or(timeturner::DEFAULT_OUTPUTS)
+                    .iter()
+                    .map(quoted),
+            )),
+        ),
+    ];
+    print_settings(settings);
+    for (name, profile) in &config.profiles {
+        println!("\n[profiles.{name}]");
+        print_profile(profile);
+    }
+    if !config.zones.is_empty() {
+        println!("\n[zones]");
+        for (alias, zone) in &config.zones {
+            println!("{alias} = {}", quoted(zone));
+        }
+    }
+}
+
+fn print_profile(profile: &Profile) {
+    print_settings([
+        (
+            "outputs",
+            profile
+                .outputs
+                .as_ref()
+                .map(|outputs| list(outputs.iter().map(quoted))),
+        ),
+        (
+            "input-timezone",
+            profile.input_timezone.as_ref().map(quoted),
+        ),
+        (
+            "output-timezone",
+            profile.output_timezone.as_ref().map(quoted),
+        ),
+        ("precision", profile.precision.as_ref().map(quoted)),
+    ]);
+}
+
+fn print_settings<const N: usize>(settings: [(&str, Option<String>); N]) {
+    for (key, value) in settings {
+        if let Some(value) = value {
+            println!("{key} = {value}");
+        }
+    }
+}
+
+fn quoted(value: &(impl Display + ?Sized)) -> String {
+    format!("{:?}", value.to_string())
+}
+
+fn named(value: &impl ValueEnum) -> String {
+    quoted(&timeturner::value_name(value))
+}
+
+fn list(values: impl Iterator<Item = String>) -> String {
+    format!("[{}]", values.collect::<Vec<_>>().join(", "))
+}
+
+fn snap(opt: &Opt, timezone: Option<TimeZoneSpec>) -> Option<Snap> {
+    let (rounding, unit) = match (opt.floor, opt.ceil, opt.round) {
+        (Some(unit), _, _) => (Rounding::Floor, unit),
+        (_, Some(unit), _) => (Rounding::Ceil, unit),
+        (_, _, Some(unit)) => (Rounding::Round, unit),
+        _ => return None,
+    };
+    Some(Snap {
+        rounding,
+        unit,
+        week_start: opt.week_start.unwrap_or_default(),
+        timezone,
+    })
+}
+
 fn output_mode(opt: &Opt) -> OutputMode {
-    if opt.alfred {
+    if opt.alfred.unwrap_or_default() {
         OutputMode::Alfred
+    } else if let Some(format) = opt.records {
+        format.into()
+    } else if let Some(shell) = opt.shell {
+        OutputMode::Shell {
+            shell,
+            prefix: opt
+                .prefix
+                .clone()
+                .unwrap_or_else(|| String::from(DEFAULT_SHELL_PREFIX)),
+        }
+    } else if let Some(path) = &opt.template_file {
+        OutputMode::Template(
+            timeturner::load_template(path).unwrap_or_else(|err| exit_with_error(&err)),
+        )
+    } else if let Some(template) = &opt.template {
+        OutputMode::Template(template.clone())
+    } else if opt.table.unwrap_or_default() {
+        OutputMode::Table
+    } else if opt.labels.unwrap_or_default() {
+        OutputMode::Labelled
     } else {
         OutputMode::ValuePerLine
     }
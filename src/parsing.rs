@@ -1,40 +1,368 @@
-use crate::{EpochUnit, TimeZoneSpec};
+use crate::duration::{self, CalendarDuration};
+use crate::{ByteOrder, EpochUnit, ExcelSystem, ParseOptions, Precision, TimeZoneSpec};
+use crate::{business, relative, snapping};
+use chrono::TimeDelta;
 use chrono::prelude::*;
 use speedate::DateTime as SpeedDateTime;
 
+/// An instant parsed from user input, along with how precisely the input was written.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct ParsedInput {
+    pub instant: DateTime<Utc>,
+    /// The sub-second precision given in the input; never `Precision::Auto`.
+    pub precision: Precision,
+    /// Set when the input was a bare time or date completed to an instant.
+    pub anchor: Option<Anchor>,
+    /// A zone named by the input itself, such as the `[Europe/Paris]` suffix of a Java
+    /// `ZonedDateTime`, used for display when no output timezone is given.
+    pub zone: Option<TimeZoneSpec>,
+    /// The offset the input was written in: its explicit offset, or else that of the input
+    /// timezone (UTC when none was given).
+    pub offset: FixedOffset,
+}
+
+/// How a bare time or date was completed to a full instant.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Anchor {
+    /// A time of day placed on `date`, taken from `--anchor` when `explicit` and today otherwise.
+    Date { date: NaiveDate, explicit: bool },
+    /// A date placed at midnight.
+    StartOfDay,
+    /// A date placed at the last nanosecond of the day.
+    EndOfDay,
+}
+
 // Formats speedate doesn't handle; all are interpreted as UTC when no input timezone is given
-const CUSTOM_UNZONED_FORMATS: [&str; 5] = [
-    "%d %b %Y %H:%M:%S%.f", // 03 Feb 2020 01:03:10.534
-    "%F %T%.f UTC",         // 2019-11-22 09:03:44.00 UTC
-    "%T UTC %F",            // 04:10:39 UTC 2020-02-17
-    "%B %d, %Y %H:%M",      // May 23, 2020 12:00
-    "%a %b %e %T UTC %Y",   // Sun Oct 27 22:03:19 UTC 2019 (Go UnixDate)
+const CUSTOM_UNZONED_FORMATS: [&str; 6] = [
+    "%d %b %Y %H:%M:%S%.f",      // 03 Feb 2020 01:03:10.534
+    "%F %T%.f UTC",              // 2019-11-22 09:03:44.00 UTC
+    "%T UTC %F",                 // 04:10:39 UTC 2020-02-17
+    "%B %d, %Y %H:%M",           // May 23, 2020 12:00
+    "%a %b %e %T UTC %Y",        // Sun Oct 27 22:03:19 UTC 2019 (Go UnixDate)
+    "%A, %B %d, %Y %I:%M:%S %p", // Saturday, November 30, 2019 9:23:40 PM (PowerShell Get-Date)
+];
+
+// Dates without a time, completed by `parse_bare_time_or_date`
+const BARE_DATE_FORMATS: [&str; 3] = [
+    "%Y-%m-%d", // 2020-03-01
+    "%Y/%m/%d", // 2020/03/01
+    "%b %e %Y", // Nov 30  2018 (ls -l, files older than six months)
+];
+
+// Formats without a year; the year is inferred by `parse_yearless_format`
+const YEARLESS_FORMATS: [&str; 2] = [
+    "%b %e %H:%M:%S%.f", // Oct 27 22:03:19 (syslog, RFC 3164)
+    "%b %e %H:%M",       // Nov 30 13:23 (ls -l)
 ];
 
-// Formats with an embedded timezone offset
-const CUSTOM_ZONED_FORMATS: [&str; 2] = [
-    "%d/%b/%Y:%T %z",       // 27/Oct/2019:22:03:19 +0000 (nginx access log)
-    "%a %b %d %Y %T GMT%z", // Sun Oct 27 2019 22:03:19 GMT-0700 (JS Date.toString(), suffix stripped)
+// Kubernetes klog / glog header without its severity letter, e.g. `1130 21:23:40.890123` from
+// `I1130 21:23:40.890123`. Like the formats above it has no year.
+const KLOG_FORMAT: &str = "%m%d %H:%M:%S%.f";
+
+// Key of the journald wall-clock field, whose value is epoch microseconds
+const JOURNALD_REALTIME_KEY: &str = "__REALTIME_TIMESTAMP=";
+
+// Zone abbreviations printed by `date`, with their UTC offsets in seconds. Ambiguous abbreviations
+// take their most common meaning: `CST` is US Central and `IST` is India.
+const ZONE_ABBREVIATIONS: [(&str, i32); 35] = [
+    ("UTC", 0),
+    ("GMT", 0),
+    ("WET", 0),
+    ("WEST", 3600),
+    ("BST", 3600),
+    ("CET", 3600),
+    ("CEST", 7200),
+    ("EET", 7200),
+    ("EEST", 10800),
+    ("MSK", 10800),
+    ("IST", 19800),
+    ("AWST", 28800),
+    ("JST", 32400),
+    ("KST", 32400),
+    ("ACST", 34200),
+    ("ACDT", 37800),
+    ("AEST", 36000),
+    ("AEDT", 39600),
+    ("NZST", 43200),
+    ("NZDT", 46800),
+    ("HST", -36000),
+    ("AKST", -32400),
+    ("AKDT", -28800),
+    ("PST", -28800),
+    ("PDT", -25200),
+    ("MST", -25200),
+    ("MDT", -21600),
+    ("CST", -21600),
+    ("CDT", -18000),
+    ("EST", -18000),
+    ("EDT", -14400),
+    ("AST", -14400),
+    ("ADT", -10800),
+    ("NST", -12600),
+    ("NDT", -9000),
+];
+
+// Formats with an embedded timezone offset. RFC 5424 syslog stamps, such as
+// `2019-11-30T21:23:40.890123+00:00`, are RFC 3339 and need no entry here.
+const CUSTOM_ZONED_FORMATS: [&str; 4] = [
+    "%d/%b/%Y:%T %z",             // 27/Oct/2019:22:03:19 +0000 (nginx access log)
+    "%a %b %d %Y %T GMT%z",       // Sun Oct 27 2019 22:03:19 GMT-0700 (JS Date, zone name stripped)
+    "%Y-%m-%d %H:%M:%S%.f %z %Z", // 2019-11-30 21:23:40.89 +0000 UTC (Go time.Time.String())
+    "%Y-%m-%d %H:%M:%S%.f %z",    // 2019-11-30 21:23:40 +0000 (Ruby Time#to_s)
 ];
 
 fn parse_custom_unzoned_format(
     input: &str,
     input_timezone: Option<TimeZoneSpec>,
-) -> Option<DateTime<Utc>> {
+) -> Option<DateTime<FixedOffset>> {
     CUSTOM_UNZONED_FORMATS.iter().find_map(|s| {
         NaiveDateTime::parse_from_str(input, s).ok().and_then(|d| {
-            Some(match input_timezone {
+            let instant = match input_timezone {
                 Some(tz) => tz.naive_to_utc(d)?,
                 None => d.and_utc(),
-            })
+            };
+            Some(in_timezone(instant, input_timezone))
         })
     })
 }
 
-fn parse_custom_zoned_format(input: &str) -> Option<DateTime<Utc>> {
+fn parse_custom_zoned_format(input: &str) -> Option<DateTime<FixedOffset>> {
     CUSTOM_ZONED_FORMATS
         .iter()
-        .find_map(|s| DateTime::parse_from_str(input, s).ok().map(|d| d.to_utc()))
+        .find_map(|s| DateTime::parse_from_str(input, s).ok())
+}
+
+// Parses `input` with the first of the user's `--input-format` patterns that matches it, reading
+// patterns without an offset in the input timezone and date-only patterns as midnight.
+fn parse_user_format(
+    input: &str,
+    formats: &[String],
+    input_timezone: Option<TimeZoneSpec>,
+) -> Option<DateTime<FixedOffset>> {
+    formats.iter().find_map(|format| {
+        if let Ok(instant) = DateTime::parse_from_str(input, format) {
+            return Some(instant);
+        }
+        let naive = NaiveDateTime::parse_from_str(input, format)
+            .or_else(|_| {
+                NaiveDate::parse_from_str(input, format).map(|d| d.and_time(NaiveTime::MIN))
+            })
+            .ok()?;
+        let instant = match input_timezone {
+            Some(tz) => tz.naive_to_utc(naive)?,
+            None => naive.and_utc(),
+        };
+        Some(in_timezone(instant, input_timezone))
+    })
+}
+
+/// The offset of `timezone` at `instant`, or UTC when `None`.
+pub(crate) fn offset_in(timezone: Option<TimeZoneSpec>, instant: &DateTime<Utc>) -> FixedOffset {
+    timezone.map_or(chrono::Offset::fix(&Utc), |tz| tz.offset_at(instant))
+}
+
+// Expresses `instant` in the offset `timezone` has at that moment, for inputs written without an
+// explicit offset.
+fn in_timezone(instant: DateTime<Utc>, timezone: Option<TimeZoneSpec>) -> DateTime<FixedOffset> {
+    instant.with_timezone(&offset_in(timezone, &instant))
+}
+
+// Parses a syslog or `ls -l` timestamp in `--year`, or else in the most recent year that puts it
+// at or before `now`. Years are counted in the input timezone, and a leap day looks back to the
+// last leap year. Like a bare time of day, a time repeated by a DST change is the earlier one and
+// a skipped time moves forward past the gap, rather than to another year.
+fn parse_yearless_format(
+    input: &str,
+    formats: &[&str],
+    now: &DateTime<Utc>,
+    options: &ParseOptions,
+) -> Option<DateTime<Utc>> {
+    let parse_in_year = |year: i32| {
+        let naive = formats.iter().find_map(|format| {
+            NaiveDateTime::parse_from_str(&format!("{year} {input}"), &format!("%Y {format}")).ok()
+        })?;
+        snapping::to_utc(naive, options.input_timezone)
+    };
+    if let Some(year) = options.year {
+        return parse_in_year(year);
+    }
+    let current_year = match options.input_timezone {
+        Some(tz) => tz.utc_to_naive(now).year(),
+        None => now.year(),
+    };
+    (0..=8)
+        .filter_map(|years_back| parse_in_year(current_year - years_back))
+        .find(|instant| instant <= now)
+}
+
+// Parses a klog/glog line header such as `I1130 21:23:40.890123 12345 main.go:42] message`. Only
+// the severity letter, date and time are read, and the year is inferred like syslog's.
+fn parse_klog_format(
+    input: &str,
+    now: &DateTime<Utc>,
+    options: &ParseOptions,
+) -> Option<DateTime<Utc>> {
+    let mut words = input.split_whitespace();
+    let date = words.next()?.strip_prefix(['I', 'W', 'E', 'F'])?;
+    let time = words.next()?;
+    if date.len() != 4 || !date.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    parse_yearless_format(&format!("{date} {time}"), &[KLOG_FORMAT], now, options)
+}
+
+// Parses a journald `__REALTIME_TIMESTAMP=1575149020890123` field as epoch microseconds.
+fn parse_journald_realtime(input: &str) -> Option<ParsedInput> {
+    let value = input.strip_prefix(JOURNALD_REALTIME_KEY)?;
+    if !value.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    parse_epoch_with_unit(value, EpochUnit::Micros)
+}
+
+// Strips the monotonic clock reading (` m=+0.000123`) that Go's `time.Time.String()` appends.
+fn strip_go_monotonic(input: &str) -> Option<&str> {
+    let (time, reading) = input.rsplit_once(" m=")?;
+    reading.starts_with(['+', '-']).then_some(time)
+}
+
+// Splits a Java `ZonedDateTime` such as `2019-11-30T21:23:40.890Z[UTC]` into the timestamp and
+// its bracketed zone.
+fn split_java_zone(input: &str) -> Option<(&str, TimeZoneSpec)> {
+    let (timestamp, zone) = input.strip_suffix(']')?.rsplit_once('[')?;
+    Some((timestamp, crate::parse_timezone_spec(zone).ok()?))
+}
+
+// Unwraps a SQL literal such as `TIMESTAMP '2019-11-30 21:23:40'`, also accepting
+// `TIMESTAMP WITH TIME ZONE '...'` and `TIMESTAMP WITHOUT TIME ZONE '...'`.
+fn strip_sql_timestamp(input: &str) -> Option<&str> {
+    let keyword = input.get(..9)?;
+    if !keyword.eq_ignore_ascii_case("TIMESTAMP") {
+        return None;
+    }
+    let literal = input[9..].trim_start();
+    let literal = ["WITH TIME ZONE", "WITHOUT TIME ZONE"]
+        .iter()
+        .find_map(|modifier| {
+            literal
+                .get(..modifier.len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case(modifier))
+                .map(|_| literal[modifier.len()..].trim_start())
+        })
+        .unwrap_or(literal);
+    literal.strip_prefix('\'')?.strip_suffix('\'')
+}
+
+// Parses a Python `datetime` repr, e.g.
+// `datetime.datetime(2019, 11, 30, 21, 23, 40, 890000, tzinfo=datetime.timezone.utc)`. The
+// `tzinfo` may be UTC, a fixed `timedelta` offset, or a named zone from `zoneinfo` or pytz;
+// without one the input timezone applies.
+fn parse_python_repr(input: &str, input_timezone: Option<TimeZoneSpec>) -> Option<ParsedInput> {
+    let arguments = input
+        .strip_prefix("datetime.")
+        .unwrap_or(input)
+        .strip_prefix("datetime(")?
+        .strip_suffix(')')?;
+    let (fields, tzinfo) = match arguments.split_once("tzinfo=") {
+        Some((fields, tzinfo)) => (fields, Some(tzinfo)),
+        None => (arguments, None),
+    };
+    let fields: Vec<u32> = fields
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(|field| field.parse().ok())
+        .collect::<Option<_>>()?;
+    let field = |index: usize| fields.get(index).copied().unwrap_or_default();
+    if !(3..=7).contains(&fields.len()) {
+        return None;
+    }
+    let naive = NaiveDate::from_ymd_opt(i32::try_from(field(0)).ok()?, field(1), field(2))?
+        .and_hms_micro_opt(field(3), field(4), field(5), field(6))?;
+    let timezone = match tzinfo {
+        Some(tzinfo) => Some(parse_python_tzinfo(tzinfo)?),
+        None => input_timezone,
+    };
+    let instant = match timezone {
+        Some(tz) => tz.naive_to_utc(naive)?,
+        None => naive.and_utc(),
+    };
+    Some(ParsedInput {
+        instant,
+        precision: if fields.len() == 7 {
+            Precision::Micros
+        } else {
+            Precision::Secs
+        },
+        anchor: None,
+        zone: None,
+        offset: offset_in(timezone, &instant),
+    })
+}
+
+fn parse_python_tzinfo(tzinfo: &str) -> Option<TimeZoneSpec> {
+    // Named zones appear quoted: ZoneInfo(key='Europe/Paris') or <DstTzInfo 'Europe/Paris' ...>
+    if let Some((_, rest)) = tzinfo.split_once('\'') {
+        let (name, _) = rest.split_once('\'')?;
+        return crate::parse_timezone_spec(name).ok();
+    }
+    if let Some((_, arguments)) = tzinfo.split_once("timedelta(") {
+        // timedelta's parameters in positional order, with their length in seconds. An offset
+        // can't have a fraction of a second, so those units must be zero.
+        const UNITS: [(&str, i32); 7] = [
+            ("days", 86_400),
+            ("seconds", 1),
+            ("microseconds", 0),
+            ("milliseconds", 0),
+            ("minutes", 60),
+            ("hours", 3600),
+            ("weeks", 604_800),
+        ];
+        let mut offset_secs: i32 = 0;
+        for (index, argument) in arguments.split(')').next()?.split(',').enumerate() {
+            let argument = argument.trim();
+            if argument.is_empty() {
+                continue;
+            }
+            let (unit_secs, value) = match argument.split_once('=') {
+                Some((name, value)) => (
+                    UNITS.iter().find(|(unit, _)| *unit == name.trim())?.1,
+                    value,
+                ),
+                None => (UNITS.get(index)?.1, argument),
+            };
+            let value: i32 = value.trim().parse().ok()?;
+            if unit_secs == 0 && value != 0 {
+                return None;
+            }
+            offset_secs = offset_secs.checked_add(value.checked_mul(unit_secs)?)?;
+        }
+        return FixedOffset::east_opt(offset_secs).map(TimeZoneSpec::Fixed);
+    }
+    tzinfo
+        .to_ascii_lowercase()
+        .contains("utc")
+        .then(|| TimeZoneSpec::Fixed(chrono::Offset::fix(&Utc)))
+}
+
+// Parses the default output of `date`, e.g. `Sat Nov 30 13:23:40 PST 2019`, using the offset of
+// the zone abbreviation.
+fn parse_date_command_format(input: &str) -> Option<DateTime<FixedOffset>> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let [weekday, month, day, time, abbreviation, year] = words.as_slice() else {
+        return None;
+    };
+    let (_, offset_secs) = ZONE_ABBREVIATIONS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(abbreviation))?;
+    let naive = NaiveDateTime::parse_from_str(
+        &format!("{weekday} {month} {day} {time} {year}"),
+        "%a %b %e %T%.f %Y",
+    )
+    .ok()?;
+    FixedOffset::east_opt(*offset_secs)?
+        .from_local_datetime(&naive)
+        .single()
 }
 
 // Strips " (Timezone Name)" suffix produced by JS Date.toString()
@@ -49,7 +377,7 @@ fn strip_js_tz_name(input: &str) -> Option<String> {
 fn speedate_to_chrono(
     dt: SpeedDateTime,
     input_timezone: Option<TimeZoneSpec>,
-) -> Option<DateTime<Utc>> {
+) -> Option<DateTime<FixedOffset>> {
     let naive = NaiveDateTime::new(
         NaiveDate::from_ymd_opt(
             dt.date.year.into(),
@@ -63,16 +391,16 @@ fn speedate_to_chrono(
             dt.time.microsecond,
         )?,
     );
-    Some(match dt.time.tz_offset {
-        Some(offset_secs) => FixedOffset::east_opt(offset_secs)?
+    if let Some(offset_secs) = dt.time.tz_offset {
+        return FixedOffset::east_opt(offset_secs)?
             .from_local_datetime(&naive)
-            .single()?
-            .to_utc(),
-        None => match input_timezone {
-            Some(tz) => tz.naive_to_utc(naive)?,
-            None => naive.and_utc(),
-        },
-    })
+            .single();
+    }
+    let instant = match input_timezone {
+        Some(tz) => tz.naive_to_utc(naive)?,
+        None => naive.and_utc(),
+    };
+    Some(in_timezone(instant, input_timezone))
 }
 
 fn replace_comma_decimal(input: &str) -> Option<String> {
@@ -98,28 +426,95 @@ fn replace_comma_decimal(input: &str) -> Option<String> {
     if changed { Some(result) } else { None }
 }
 
+// Parses a signed integer in decimal, or in hex/octal/binary when given a `0x`/`0o`/`0b` prefix.
+// A leading minus sign is accepted for all radixes (e.g. `-0x10`).
+fn parse_integer(input: &str) -> Option<i64> {
+    let (negative, unsigned) = match input.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, input),
+    };
+    let (radix, digits) = match unsigned.get(..2) {
+        Some("0x" | "0X") => (16, &unsigned[2..]),
+        Some("0o" | "0O") => (8, &unsigned[2..]),
+        Some("0b" | "0B") => (2, &unsigned[2..]),
+        _ => return input.parse().ok(),
+    };
+    if !digits.chars().all(|c| c.is_digit(radix)) || digits.is_empty() {
+        return None;
+    }
+    let value = i64::from_str_radix(digits, radix).ok()?;
+    Some(if negative { -value } else { value })
+}
+
+// Decodes a raw 4- or 8-byte hex dump (e.g. `5c dd e2 5d` or `0x5cdde25d`) in the given byte order.
+// Whitespace and `:` separators between bytes are ignored. 4-byte values are treated as unsigned
+// 32-bit epochs; 8-byte values as signed 64-bit.
+fn parse_hex_bytes(input: &str, byte_order: ByteOrder) -> Option<i64> {
+    let hex: String = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input)
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ':')
+        .collect();
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let bytes: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+        })
+        .collect::<Option<_>>()?;
+    match (byte_order, bytes.len()) {
+        (ByteOrder::Be, 4) => Some(u32::from_be_bytes(bytes.try_into().ok()?).into()),
+        (ByteOrder::Le, 4) => Some(u32::from_le_bytes(bytes.try_into().ok()?).into()),
+        (ByteOrder::Be, 8) => Some(i64::from_be_bytes(bytes.try_into().ok()?)),
+        (ByteOrder::Le, 8) => Some(i64::from_le_bytes(bytes.try_into().ok()?)),
+        _ => None,
+    }
+}
+
 // Parses a signed integer string as an epoch value in the given unit.
 // Returns None if the input is not a valid integer.
-fn parse_epoch_with_unit(input: &str, unit: EpochUnit) -> Option<DateTime<Utc>> {
-    let value: i64 = input.parse().ok()?;
-    epoch_value_to_datetime(value, unit)
+fn parse_epoch_with_unit(input: &str, unit: EpochUnit) -> Option<ParsedInput> {
+    let value = parse_integer(input)?;
+    Some(ParsedInput {
+        instant: epoch_value_to_datetime(value, unit)?,
+        precision: unit.into(),
+        anchor: None,
+        zone: None,
+        offset: chrono::Offset::fix(&Utc),
+    })
+}
+
+// Parses pure-(signed-)integer epoch strings, inferring the unit from the value's magnitude.
+// See `infer_epoch_unit` for the ranges.
+fn parse_epoch_auto(input: &str) -> Option<ParsedInput> {
+    let value = parse_integer(input)?;
+    let unit = infer_epoch_unit(value);
+    Some(ParsedInput {
+        instant: epoch_value_to_datetime(value, unit)?,
+        precision: unit.into(),
+        anchor: None,
+        zone: None,
+        offset: chrono::Offset::fix(&Utc),
+    })
 }
 
-// Parses pure-(signed-)integer epoch strings, inferring the unit from the value's magnitude:
+// Infers the unit of an epoch value from its magnitude:
 //   abs(value) < 1e11  → seconds    (covers 1970 → ~year 5138)
 //   abs(value) < 1e14  → millis     (covers 1973 → ~year 5138)
 //   abs(value) < 1e17  → micros     (covers 1973 → ~year 5138)
 //   abs(value) >= 1e17 → nanos      (covers 1973 → 2262, capped by i64::MAX)
-// Supports a leading minus sign for pre-1970 values.
-fn parse_epoch_auto(input: &str) -> Option<DateTime<Utc>> {
-    let value: i64 = input.parse().ok()?;
-    let unit = match value.unsigned_abs() {
+fn infer_epoch_unit(value: i64) -> EpochUnit {
+    match value.unsigned_abs() {
         v if v < 100_000_000_000 => EpochUnit::Seconds,
         v if v < 100_000_000_000_000 => EpochUnit::Millis,
         v if v < 100_000_000_000_000_000 => EpochUnit::Micros,
         _ => EpochUnit::Nanos,
-    };
-    epoch_value_to_datetime(value, unit)
+    }
 }
 
 fn epoch_value_to_datetime(value: i64, unit: EpochUnit) -> Option<DateTime<Utc>> {
@@ -131,6 +526,42 @@ fn epoch_value_to_datetime(value: i64, unit: EpochUnit) -> Option<DateTime<Utc>>
     }
 }
 
+// Excel's 1900 system counts 1900-02-29 as a day, although 1900 was not a leap year, so serials
+// from 61 on are one day further from 1900-01-01 than they appear. Serial 60 is that missing day.
+fn parse_excel_serial(
+    input: &str,
+    system: ExcelSystem,
+    timezone: Option<TimeZoneSpec>,
+) -> Option<ParsedInput> {
+    let serial: f64 = input.trim().parse().ok()?;
+    if !serial.is_finite() || serial < 0.0 {
+        return None;
+    }
+    // Excel keeps the time of day to the millisecond
+    #[allow(clippy::cast_possible_truncation)]
+    let (days, millis) = (
+        serial.trunc() as i64,
+        (serial.fract() * 86_400_000.0).round() as i64,
+    );
+    let epoch = match system {
+        ExcelSystem::From1900 if days == 60 => return None,
+        ExcelSystem::From1900 if days > 60 => NaiveDate::from_ymd_opt(1899, 12, 30)?,
+        ExcelSystem::From1900 => NaiveDate::from_ymd_opt(1899, 12, 31)?,
+        ExcelSystem::From1904 => NaiveDate::from_ymd_opt(1904, 1, 1)?,
+    };
+    let since_epoch = TimeDelta::try_days(days)?.checked_add(&TimeDelta::milliseconds(millis))?;
+    let local = epoch
+        .and_time(NaiveTime::MIN)
+        .checked_add_signed(since_epoch)?;
+    snapping::to_utc(local, timezone).map(|instant| ParsedInput {
+        instant,
+        precision: Precision::Millis,
+        anchor: None,
+        zone: None,
+        offset: offset_in(timezone, &instant),
+    })
+}
+
 fn parse_with_dateparser(
     input: &str,
     input_timezone: Option<TimeZoneSpec>,
@@ -142,37 +573,296 @@ fn parse_with_dateparser(
     }
 }
 
-fn parse_with_speedate(input: &str, input_timezone: Option<TimeZoneSpec>) -> Option<DateTime<Utc>> {
+fn parse_with_speedate(
+    input: &str,
+    input_timezone: Option<TimeZoneSpec>,
+) -> Option<DateTime<FixedOffset>> {
     SpeedDateTime::parse_str(input)
         .ok()
         .and_then(|dt| speedate_to_chrono(dt, input_timezone))
 }
 
+// The right-hand side of date arithmetic.
+enum Offset {
+    Duration(CalendarDuration),
+    BusinessDays(i64),
+}
+
+// Splits `<instant> + <duration>` or `<instant> - <duration>` at the last operator. The operator
+// must be surrounded by spaces so it can't be confused with date separators or offsets, and the
+// right-hand side must parse as an ISO 8601 or humantime duration. A count of business days such
+// as `5bd` can't be mistaken for either, so it may also follow the operator directly (`+5bd`).
+fn split_arithmetic(input: &str) -> Option<(&str, Offset)> {
+    if let Some(count) = input.trim_end().strip_suffix("bd") {
+        let pos = count.rfind(['+', '-'])?;
+        let days: i64 = count[pos + 1..].trim().parse().ok()?;
+        let subtract = count[pos..].starts_with('-');
+        return Some((
+            input[..pos].trim(),
+            Offset::BusinessDays(if subtract { -days } else { days }),
+        ));
+    }
+    let (pos, subtract) = [(input.rfind(" + "), false), (input.rfind(" - "), true)]
+        .into_iter()
+        .filter_map(|(pos, subtract)| pos.map(|p| (p, subtract)))
+        .max_by_key(|(pos, _)| *pos)?;
+    let duration = duration::parse_duration(input[pos + 3..].trim())?;
+    Some((
+        input[..pos].trim(),
+        Offset::Duration(if subtract { -duration } else { duration }),
+    ))
+}
+
+// Returns the fractional-second digits written after the first `:SS` in `input`, e.g. `747` for
+// `2019-10-27T22:03:19.747Z`. A comma is accepted as the decimal separator.
+fn subsec_digits(input: &str) -> &str {
+    input
+        .match_indices(':')
+        .find_map(|(pos, _)| {
+            let rest = &input[pos + 1..];
+            let seconds = rest.get(..2)?;
+            let fraction = rest[2..].strip_prefix(['.', ','])?;
+            if !seconds.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let end = fraction
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(fraction.len());
+            (end > 0).then(|| &fraction[..end])
+        })
+        .unwrap_or_default()
+}
+
+// Records the precision written in a textual input. speedate stops at microseconds, so when more
+// digits were given the nanoseconds are restored from the text.
+fn with_text_precision(input: &str, instant: DateTime<FixedOffset>) -> ParsedInput {
+    let digits = subsec_digits(input);
+    let instant = if digits.len() > 6 {
+        let nanos: u32 = format!("{digits:0<9.9}").parse().unwrap_or_default();
+        if instant.timestamp_subsec_micros() == nanos / 1000 {
+            instant.with_nanosecond(nanos).unwrap_or(instant)
+        } else {
+            instant
+        }
+    } else {
+        instant
+    };
+    ParsedInput {
+        instant: instant.to_utc(),
+        precision: Precision::from_subsec_digits(digits.len()),
+        anchor: None,
+        zone: None,
+        offset: *instant.offset(),
+    }
+}
+
+// Completes a time of day without a date (`14:30`, `09:15:00.250`, `2pm`) onto the `--anchor` date
+// or today, or a date without a time (`2020-03-01`) to the start or end of that day. Both are
+// interpreted in the input timezone.
+fn parse_bare_time_or_date(
+    input: &str,
+    now: &DateTime<Utc>,
+    options: &ParseOptions,
+) -> Option<ParsedInput> {
+    let timezone = options.input_timezone;
+    let time = NaiveTime::parse_from_str(input, "%H:%M:%S%.f")
+        .ok()
+        .or_else(|| relative::parse_time_of_day(input));
+    if let Some(time) = time {
+        let today = match timezone {
+            Some(tz) => tz.utc_to_naive(now),
+            None => now.naive_utc(),
+        }
+        .date();
+        let date = options.anchor.unwrap_or(today);
+        let instant = snapping::to_utc(date.and_time(time), timezone)?;
+        return Some(ParsedInput {
+            anchor: Some(Anchor::Date {
+                date,
+                explicit: options.anchor.is_some(),
+            }),
+            ..with_text_precision(input, in_timezone(instant, timezone))
+        });
+    }
+    let date = BARE_DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(input, format).ok())?;
+    let (time, precision, anchor) = if options.end_of_day {
+        (
+            NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999)?,
+            Precision::Nanos,
+            Anchor::EndOfDay,
+        )
+    } else {
+        (NaiveTime::MIN, Precision::Secs, Anchor::StartOfDay)
+    };
+    let instant = snapping::to_utc(date.and_time(time), timezone)?;
+    Some(ParsedInput {
+        instant,
+        precision,
+        anchor: Some(anchor),
+        zone: None,
+        offset: offset_in(timezone, &instant),
+    })
+}
+
+/// Describes how `anchor` completed the input in `timezone` (UTC when `None`), for `--explain`.
+#[must_use]
+pub fn describe_anchor(anchor: Option<Anchor>, timezone: Option<TimeZoneSpec>) -> String {
+    let zone = match timezone {
+        Some(TimeZoneSpec::Named(tz)) => tz.name().to_string(),
+        Some(TimeZoneSpec::Fixed(offset)) => offset.to_string(),
+        None => String::from("UTC"),
+    };
+    match anchor {
+        Some(Anchor::Date {
+            date,
+            explicit: true,
+        }) => format!("Time of day anchored to {date} from --anchor, in {zone}"),
+        Some(Anchor::Date {
+            date,
+            explicit: false,
+        }) => format!("Time of day anchored to today, {date}, in {zone}"),
+        Some(Anchor::StartOfDay) => {
+            format!("Date anchored to the start of the day (00:00:00) in {zone}")
+        }
+        Some(Anchor::EndOfDay) => {
+            format!("Date anchored to the end of the day (23:59:59.999999999) in {zone}")
+        }
+        None => String::from("No anchor applied, the input names a complete instant"),
+    }
+}
+
+fn parse_instant(
+    input: &str,
+    now: &DateTime<Utc>,
+    options: &ParseOptions,
+) -> Result<ParsedInput, &'static str> {
+    let input_timezone = options.input_timezone;
+    if let Some(byte_order) = options.byte_order {
+        let value = parse_hex_bytes(input, byte_order)
+            .ok_or("--byte-order requires a 4 or 8 byte hex input")?;
+        let unit = options
+            .epoch_unit
+            .unwrap_or_else(|| infer_epoch_unit(value));
+        return epoch_value_to_datetime(value, unit)
+            .map(|instant| ParsedInput {
+                instant,
+                precision: unit.into(),
+                anchor: None,
+                zone: None,
+                offset: chrono::Offset::fix(&Utc),
+            })
+            .ok_or("Epoch value out of range");
+    }
+    if let Some(system) = options.excel {
+        return parse_excel_serial(input, system, input_timezone)
+            .ok_or("--excel requires a serial date such as 43799.89144");
+    }
+    if let Some(unit) = options.epoch_unit {
+        return parse_epoch_with_unit(input, unit)
+            .ok_or("--epoch-unit requires a numeric epoch input");
+    }
+    if let Some(instant) = parse_user_format(input, &options.input_formats, input_timezone) {
+        return Ok(with_text_precision(input, instant));
+    }
+    if let Some(parsed) = parse_epoch_auto(input).or_else(|| parse_journald_realtime(input)) {
+        return Ok(parsed);
+    }
+    if let Some(parsed) = parse_bare_time_or_date(input, now, options) {
+        return Ok(parsed);
+    }
+    if let Some((timestamp, zone)) = split_java_zone(input) {
+        let options = ParseOptions {
+            input_timezone: Some(zone),
+            ..options.clone()
+        };
+        return parse_instant(timestamp, now, &options).map(|parsed| ParsedInput {
+            zone: Some(zone),
+            ..parsed
+        });
+    }
+    if let Some(literal) = strip_sql_timestamp(input) {
+        return parse_instant(literal, now, options);
+    }
+    if let Some(parsed) = parse_python_repr(input, input_timezone) {
+        return Ok(parsed);
+    }
+    if let Some(parsed) = relative::parse_relative(input, now, input_timezone) {
+        return Ok(parsed);
+    }
+    parse_with_speedate(input, input_timezone)
+        .or_else(|| parse_custom_unzoned_format(input, input_timezone))
+        .or_else(|| {
+            replace_comma_decimal(input).and_then(|normalized| {
+                parse_with_speedate(&normalized, input_timezone)
+                    .or_else(|| parse_custom_unzoned_format(&normalized, input_timezone))
+            })
+        })
+        .or_else(|| parse_custom_zoned_format(input))
+        .or_else(|| strip_js_tz_name(input).and_then(|s| parse_custom_zoned_format(&s)))
+        .or_else(|| strip_go_monotonic(input).and_then(parse_custom_zoned_format))
+        .or_else(|| {
+            parse_yearless_format(input, &YEARLESS_FORMATS, now, options)
+                .or_else(|| parse_klog_format(input, now, options))
+                .map(|instant| in_timezone(instant, input_timezone))
+        })
+        .or_else(|| parse_date_command_format(input))
+        .or_else(|| {
+            parse_with_dateparser(input, input_timezone)
+                .map(|instant| in_timezone(instant, input_timezone))
+        })
+        .map(|instant| with_text_precision(input, instant))
+        .ok_or("Input format not recognized")
+}
+
+/// Parses `input` to an instant, or returns `now` when it is missing or blank.
+/// Relative phrases such as `yesterday` or `3 days ago` are resolved against `now`.
+/// Inputs of the form `<instant> + <duration>` / `<instant> - <duration>` are evaluated, where
+/// `<instant>` may be any input including `now`; calendar parts of the duration and business days
+/// (`+5bd`) are applied in the input timezone.
 pub fn parse_input(
     input: Option<&str>,
-    epoch_unit: Option<EpochUnit>,
-    input_timezone: Option<TimeZoneSpec>,
-) -> Result<DateTime<Utc>, &'static str> {
+    now: &DateTime<Utc>,
+    options: &ParseOptions,
+) -> Result<ParsedInput, &'static str> {
     input.map(str::trim).filter(|i| !i.is_empty()).map_or_else(
-        || Ok(Utc::now()),
+        || {
+            Ok(ParsedInput {
+                instant: *now,
+                precision: Precision::Millis,
+                anchor: None,
+                zone: None,
+                offset: offset_in(options.input_timezone, now),
+            })
+        },
         |i| {
-            if let Some(unit) = epoch_unit {
-                return parse_epoch_with_unit(i, unit)
-                    .ok_or("--epoch-unit requires a numeric epoch input");
-            }
-            parse_epoch_auto(i)
-                .or_else(|| parse_with_speedate(i, input_timezone))
-                .or_else(|| parse_custom_unzoned_format(i, input_timezone))
-                .or_else(|| {
-                    replace_comma_decimal(i).and_then(|normalized| {
-                        parse_with_speedate(&normalized, input_timezone)
-                            .or_else(|| parse_custom_unzoned_format(&normalized, input_timezone))
-                    })
-                })
-                .or_else(|| parse_custom_zoned_format(i))
-                .or_else(|| strip_js_tz_name(i).and_then(|s| parse_custom_zoned_format(&s)))
-                .or_else(|| parse_with_dateparser(i, input_timezone))
-                .ok_or("Input format not recognized")
+            let Some((base, offset)) = split_arithmetic(i) else {
+                return parse_instant(i, now, options);
+            };
+            let base = parse_instant(base, now, options)?;
+            let instant = match offset {
+                Offset::Duration(duration) => {
+                    duration.add_to(&base.instant, options.input_timezone)
+                }
+                Offset::BusinessDays(days) => business::add_business_days(
+                    &base.instant,
+                    days,
+                    &options.business_calendar,
+                    options.input_timezone,
+                ),
+            };
+            let instant = instant.ok_or("Date arithmetic result out of range")?;
+            // An offset that came from a zone follows it across a DST change, while one written
+            // in the input is kept
+            let zone = base.zone.or(options
+                .input_timezone
+                .filter(|tz| tz.offset_at(&base.instant) == base.offset));
+            Ok(ParsedInput {
+                instant,
+                offset: zone.map_or(base.offset, |tz| tz.offset_at(&instant)),
+                ..base
+            })
         },
     )
 }
@@ -183,6 +873,13 @@ mod tests {
 
     use super::*;
 
+    fn parse_input_instant(
+        input: Option<&str>,
+        options: &ParseOptions,
+    ) -> Result<DateTime<Utc>, &'static str> {
+        parse_input(input, &Utc::now(), options).map(|parsed| parsed.instant)
+    }
+
     fn expected_from_millis(millis: i64) -> Result<DateTime<Utc>, &'static str> {
         Utc.timestamp_millis_opt(millis)
             .single()
@@ -192,7 +889,7 @@ mod tests {
     #[test]
     fn missing_input() {
         let now = Utc::now();
-        let result = parse_input(None, None, None).unwrap();
+        let result = parse_input_instant(None, &ParseOptions::default()).unwrap();
         assert!(
             result.timestamp_millis() >= now.timestamp_millis(),
             "Provided time {result} was not after the start of the test {now}"
@@ -207,7 +904,8 @@ mod tests {
     #[test]
     fn empty_input() {
         let now = Utc::now();
-        let result = parse_input(Some(&String::from(" ")), None, None).unwrap();
+        let result =
+            parse_input_instant(Some(&String::from(" ")), &ParseOptions::default()).unwrap();
         assert!(
             result.timestamp_millis() >= now.timestamp_millis(),
             "Provided time {result} was not after the start of the test {now}"
@@ -222,7 +920,7 @@ mod tests {
     #[test]
     fn epoch_millis_input() {
         assert_eq!(
-            parse_input(Some("1572213799747"), None, None),
+            parse_input_instant(Some("1572213799747"), &ParseOptions::default()),
             expected_from_millis(1572213799747),
         );
     }
@@ -230,7 +928,7 @@ mod tests {
     #[test]
     fn epoch_micros_input() {
         assert_eq!(
-            parse_input(Some("1572213799747000"), None, None),
+            parse_input_instant(Some("1572213799747000"), &ParseOptions::default()),
             expected_from_millis(1572213799747),
         );
     }
@@ -238,7 +936,7 @@ mod tests {
     #[test]
     fn epoch_nanos_input() {
         assert_eq!(
-            parse_input(Some("1572213799747000000"), None, None),
+            parse_input_instant(Some("1572213799747000000"), &ParseOptions::default()),
             expected_from_millis(1572213799747),
         );
     }
@@ -247,7 +945,7 @@ mod tests {
     #[test]
     fn epoch_micros_min_16_digit() {
         assert_eq!(
-            parse_input(Some("1000000000000000"), None, None),
+            parse_input_instant(Some("1000000000000000"), &ParseOptions::default()),
             expected_from_millis(1000000000000),
         );
     }
@@ -256,7 +954,7 @@ mod tests {
     #[test]
     fn epoch_nanos_min_19_digit() {
         assert_eq!(
-            parse_input(Some("1000000000000000000"), None, None),
+            parse_input_instant(Some("1000000000000000000"), &ParseOptions::default()),
             expected_from_millis(1000000000000),
         );
     }
@@ -266,7 +964,7 @@ mod tests {
     #[test]
     fn epoch_micros_pre_2001() {
         assert_eq!(
-            parse_input(Some("946684800000000"), None, None),
+            parse_input_instant(Some("946684800000000"), &ParseOptions::default()),
             expected_from_millis(946684800000),
         );
     }
@@ -275,7 +973,7 @@ mod tests {
     #[test]
     fn epoch_nanos_pre_2001() {
         assert_eq!(
-            parse_input(Some("946684800000000000"), None, None),
+            parse_input_instant(Some("946684800000000000"), &ParseOptions::default()),
             expected_from_millis(946684800000),
         );
     }
@@ -284,7 +982,7 @@ mod tests {
     #[test]
     fn epoch_seconds_auto() {
         assert_eq!(
-            parse_input(Some("1572213799"), None, None),
+            parse_input_instant(Some("1572213799"), &ParseOptions::default()),
             expected_from_millis(1572213799000),
         );
     }
@@ -293,7 +991,7 @@ mod tests {
     #[test]
     fn epoch_negative_seconds() {
         assert_eq!(
-            parse_input(Some("-1"), None, None),
+            parse_input_instant(Some("-1"), &ParseOptions::default()),
             expected_from_millis(-1000)
         );
     }
@@ -302,7 +1000,13 @@ mod tests {
     #[test]
     fn epoch_forced_micros_14_digit() {
         assert_eq!(
-            parse_input(Some("10000000000000"), Some(EpochUnit::Micros), None),
+            parse_input_instant(
+                Some("10000000000000"),
+                &ParseOptions {
+                    epoch_unit: Some(EpochUnit::Micros),
+                    ..ParseOptions::default()
+                }
+            ),
             expected_from_millis(10000000000),
         );
     }
@@ -311,7 +1015,13 @@ mod tests {
     #[test]
     fn epoch_forced_seconds_short() {
         assert_eq!(
-            parse_input(Some("60"), Some(EpochUnit::Seconds), None),
+            parse_input_instant(
+                Some("60"),
+                &ParseOptions {
+                    epoch_unit: Some(EpochUnit::Seconds),
+                    ..ParseOptions::default()
+                }
+            ),
             expected_from_millis(60000),
         );
     }
@@ -320,7 +1030,13 @@ mod tests {
     #[test]
     fn epoch_forced_rejects_non_numeric() {
         assert_eq!(
-            parse_input(Some("2020-01-01"), Some(EpochUnit::Millis), None),
+            parse_input_instant(
+                Some("2020-01-01"),
+                &ParseOptions {
+                    epoch_unit: Some(EpochUnit::Millis),
+                    ..ParseOptions::default()
+                }
+            ),
             Err("--epoch-unit requires a numeric epoch input"),
         );
     }
@@ -328,7 +1044,10 @@ mod tests {
     #[test]
     fn rfc3339_input() {
         assert_eq!(
-            parse_input(Some("2019-10-27T15:03:19.747-07:00"), None, None),
+            parse_input_instant(
+                Some("2019-10-27T15:03:19.747-07:00"),
+                &ParseOptions::default()
+            ),
             expected_from_millis(1572213799747),
         );
     }
@@ -336,7 +1055,7 @@ mod tests {
     #[test]
     fn rfc3339_input_no_partial_seconds() {
         assert_eq!(
-            parse_input(Some("2019-10-27T15:03:19-07:00"), None, None),
+            parse_input_instant(Some("2019-10-27T15:03:19-07:00"), &ParseOptions::default()),
             expected_from_millis(1572213799000),
         );
     }
@@ -344,7 +1063,7 @@ mod tests {
     #[test]
     fn rfc3339_input_zulu() {
         assert_eq!(
-            parse_input(Some("2019-10-27T22:03:19.747Z"), None, None),
+            parse_input_instant(Some("2019-10-27T22:03:19.747Z"), &ParseOptions::default()),
             expected_from_millis(1572213799747),
         );
     }
@@ -352,7 +1071,10 @@ mod tests {
     #[test]
     fn rfc3339_input_space_instead_of_t() {
         assert_eq!(
-            parse_input(Some("2019-10-27 15:03:19.747-07:00"), None, None),
+            parse_input_instant(
+                Some("2019-10-27 15:03:19.747-07:00"),
+                &ParseOptions::default()
+            ),
             expected_from_millis(1572213799747),
         );
     }
@@ -360,7 +1082,10 @@ mod tests {
     #[test]
     fn rfc3339_input_lowercase_t() {
         assert_eq!(
-            parse_input(Some("2019-10-27t15:03:19.747-07:00"), None, None),
+            parse_input_instant(
+                Some("2019-10-27t15:03:19.747-07:00"),
+                &ParseOptions::default()
+            ),
             expected_from_millis(1572213799747),
         );
     }
@@ -368,7 +1093,7 @@ mod tests {
     #[test]
     fn rfc3339_no_offset_assumed_utc() {
         assert_eq!(
-            parse_input(Some("2019-10-27T22:03:19.747"), None, None),
+            parse_input_instant(Some("2019-10-27T22:03:19.747"), &ParseOptions::default()),
             expected_from_millis(1572213799747),
         );
     }
@@ -376,7 +1101,7 @@ mod tests {
     #[test]
     fn rfc3339_no_offset_no_millis_assumed_utc() {
         assert_eq!(
-            parse_input(Some("2019-10-27T22:03:19"), None, None),
+            parse_input_instant(Some("2019-10-27T22:03:19"), &ParseOptions::default()),
             expected_from_millis(1572213799000),
         );
     }
@@ -384,7 +1109,7 @@ mod tests {
     #[test]
     fn rfc3339_lowercase_t_no_offset_assumed_utc() {
         assert_eq!(
-            parse_input(Some("2019-10-27t22:03:19.747"), None, None),
+            parse_input_instant(Some("2019-10-27t22:03:19.747"), &ParseOptions::default()),
             expected_from_millis(1572213799747),
         );
     }
@@ -392,7 +1117,7 @@ mod tests {
     #[test]
     fn rfc3339_space_separator_no_offset_assumed_utc() {
         assert_eq!(
-            parse_input(Some("2019-10-27 22:03:19.747"), None, None),
+            parse_input_instant(Some("2019-10-27 22:03:19.747"), &ParseOptions::default()),
             expected_from_millis(1572213799747),
         );
     }
@@ -400,7 +1125,7 @@ mod tests {
     #[test]
     fn custom_unzoned_rfc3339_like_with_space_and_comma() {
         assert_eq!(
-            parse_input(Some("2020-12-17 00:00:34,247"), None, None),
+            parse_input_instant(Some("2020-12-17 00:00:34,247"), &ParseOptions::default()),
             expected_from_millis(1608163234247),
         );
     }
@@ -408,7 +1133,7 @@ mod tests {
     #[test]
     fn rfc3339_input_comma_decimal_zulu() {
         assert_eq!(
-            parse_input(Some("2019-10-27T22:03:19,747Z"), None, None),
+            parse_input_instant(Some("2019-10-27T22:03:19,747Z"), &ParseOptions::default()),
             expected_from_millis(1572213799747),
         );
     }
@@ -416,7 +1141,10 @@ mod tests {
     #[test]
     fn rfc3339_input_comma_decimal_with_offset() {
         assert_eq!(
-            parse_input(Some("2019-10-27T15:03:19,747-07:00"), None, None),
+            parse_input_instant(
+                Some("2019-10-27T15:03:19,747-07:00"),
+                &ParseOptions::default()
+            ),
             expected_from_millis(1572213799747),
         );
     }
@@ -424,7 +1152,7 @@ mod tests {
     #[test]
     fn date_spelled_short_month_time_with_dot_input() {
         assert_eq!(
-            parse_input(Some("03 Feb 2020 01:03:10.534"), None, None),
+            parse_input_instant(Some("03 Feb 2020 01:03:10.534"), &ParseOptions::default()),
             expected_from_millis(1580691790534),
         );
     }
@@ -432,7 +1160,7 @@ mod tests {
     #[test]
     fn date_spelled_short_month_time_with_comma_input() {
         assert_eq!(
-            parse_input(Some("03 Feb 2020 01:03:10,534"), None, None),
+            parse_input_instant(Some("03 Feb 2020 01:03:10,534"), &ParseOptions::default()),
             expected_from_millis(1580691790534),
         );
     }
@@ -440,7 +1168,7 @@ mod tests {
     #[test]
     fn year_space_date_space_utc() {
         assert_eq!(
-            parse_input(Some("2019-11-22 09:03:44.00 UTC"), None, None),
+            parse_input_instant(Some("2019-11-22 09:03:44.00 UTC"), &ParseOptions::default()),
             expected_from_millis(1574413424000),
         );
     }
@@ -448,7 +1176,7 @@ mod tests {
     #[test]
     fn time_space_utc_space_date() {
         assert_eq!(
-            parse_input(Some("04:10:39 UTC 2020-02-17"), None, None),
+            parse_input_instant(Some("04:10:39 UTC 2020-02-17"), &ParseOptions::default()),
             expected_from_millis(1581912639000),
         );
     }
@@ -456,7 +1184,7 @@ mod tests {
     #[test]
     fn test_casssandra_zoned_no_millis() {
         assert_eq!(
-            parse_input(Some("2015-03-07 00:59:56+0100"), None, None),
+            parse_input_instant(Some("2015-03-07 00:59:56+0100"), &ParseOptions::default()),
             expected_from_millis(1425686396000),
         );
     }
@@ -464,7 +1192,10 @@ mod tests {
     #[test]
     fn test_casssandra_zoned_millis() {
         assert_eq!(
-            parse_input(Some("2015-03-07 00:59:56.001+0100"), None, None),
+            parse_input_instant(
+                Some("2015-03-07 00:59:56.001+0100"),
+                &ParseOptions::default()
+            ),
             expected_from_millis(1425686396001),
         );
     }
@@ -472,7 +1203,7 @@ mod tests {
     #[test]
     fn test_mysql_datetime() {
         assert_eq!(
-            parse_input(Some("2021-01-20 18:13:37.842000"), None, None),
+            parse_input_instant(Some("2021-01-20 18:13:37.842000"), &ParseOptions::default()),
             expected_from_millis(1611166417842),
         );
     }
@@ -480,7 +1211,7 @@ mod tests {
     #[test]
     fn english_input() {
         assert_eq!(
-            parse_input(Some("May 23, 2020 12:00"), None, None),
+            parse_input_instant(Some("May 23, 2020 12:00"), &ParseOptions::default()),
             expected_from_millis(1590235200000),
         );
     }
@@ -488,7 +1219,7 @@ mod tests {
     #[test]
     fn invalid_input() {
         assert_eq!(
-            parse_input(Some("not a date"), None, None),
+            parse_input_instant(Some("not a date"), &ParseOptions::default()),
             Err("Input format not recognized"),
         );
     }
@@ -497,7 +1228,7 @@ mod tests {
     #[test]
     fn nginx_access_log_format() {
         assert_eq!(
-            parse_input(Some("27/Oct/2019:22:03:19 +0000"), None, None),
+            parse_input_instant(Some("27/Oct/2019:22:03:19 +0000"), &ParseOptions::default()),
             expected_from_millis(1572213799000),
         );
     }
@@ -505,7 +1236,7 @@ mod tests {
     #[test]
     fn nginx_access_log_format_nonzero_offset() {
         assert_eq!(
-            parse_input(Some("27/Oct/2019:15:03:19 -0700"), None, None),
+            parse_input_instant(Some("27/Oct/2019:15:03:19 -0700"), &ParseOptions::default()),
             expected_from_millis(1572213799000),
         );
     }
@@ -514,7 +1245,10 @@ mod tests {
     #[test]
     fn http_date_rfc7231() {
         assert_eq!(
-            parse_input(Some("Sun, 27 Oct 2019 22:03:19 GMT"), None, None),
+            parse_input_instant(
+                Some("Sun, 27 Oct 2019 22:03:19 GMT"),
+                &ParseOptions::default()
+            ),
             expected_from_millis(1572213799000),
         );
     }
@@ -523,7 +1257,10 @@ mod tests {
     #[test]
     fn rfc2822_numeric_utc_offset() {
         assert_eq!(
-            parse_input(Some("Sun, 27 Oct 2019 22:03:19 +0000"), None, None),
+            parse_input_instant(
+                Some("Sun, 27 Oct 2019 22:03:19 +0000"),
+                &ParseOptions::default()
+            ),
             expected_from_millis(1572213799000),
         );
     }
@@ -531,7 +1268,10 @@ mod tests {
     #[test]
     fn rfc2822_nonzero_offset() {
         assert_eq!(
-            parse_input(Some("Sun, 27 Oct 2019 15:03:19 -0700"), None, None),
+            parse_input_instant(
+                Some("Sun, 27 Oct 2019 15:03:19 -0700"),
+                &ParseOptions::default()
+            ),
             expected_from_millis(1572213799000),
         );
     }
@@ -540,7 +1280,10 @@ mod tests {
     #[test]
     fn go_unix_date_format() {
         assert_eq!(
-            parse_input(Some("Sun Oct 27 22:03:19 UTC 2019"), None, None),
+            parse_input_instant(
+                Some("Sun Oct 27 22:03:19 UTC 2019"),
+                &ParseOptions::default()
+            ),
             expected_from_millis(1572213799000),
         );
     }
@@ -549,10 +1292,9 @@ mod tests {
     #[test]
     fn javascript_date_tostring_utc() {
         assert_eq!(
-            parse_input(
+            parse_input_instant(
                 Some("Sun Oct 27 2019 22:03:19 GMT+0000 (Coordinated Universal Time)"),
-                None,
-                None,
+                &ParseOptions::default(),
             ),
             expected_from_millis(1572213799000),
         );
@@ -561,10 +1303,9 @@ mod tests {
     #[test]
     fn javascript_date_tostring_nonzero_offset() {
         assert_eq!(
-            parse_input(
+            parse_input_instant(
                 Some("Sun Oct 27 2019 15:03:19 GMT-0700 (Pacific Daylight Time)"),
-                None,
-                None,
+                &ParseOptions::default(),
             ),
             expected_from_millis(1572213799000),
         );
@@ -575,7 +1316,13 @@ mod tests {
     fn naive_input_with_input_tz_named() {
         let tz = crate::parse_timezone_spec("America/Los_Angeles").unwrap();
         assert_eq!(
-            parse_input(Some("2019-10-27T15:03:19"), None, Some(tz)),
+            parse_input_instant(
+                Some("2019-10-27T15:03:19"),
+                &ParseOptions {
+                    input_timezone: Some(tz),
+                    ..ParseOptions::default()
+                }
+            ),
             expected_from_millis(1572213799000),
         );
     }
@@ -584,7 +1331,13 @@ mod tests {
     fn naive_input_with_input_tz_fixed_offset() {
         let tz = crate::parse_timezone_spec("-07:00").unwrap();
         assert_eq!(
-            parse_input(Some("2019-10-27T15:03:19"), None, Some(tz)),
+            parse_input_instant(
+                Some("2019-10-27T15:03:19"),
+                &ParseOptions {
+                    input_timezone: Some(tz),
+                    ..ParseOptions::default()
+                }
+            ),
             expected_from_millis(1572213799000),
         );
     }
@@ -595,7 +1348,13 @@ mod tests {
         // With +09:00, it's 9 hours earlier in UTC → 1580691790534 - 9*3600*1000 = 1580659390534
         let tz = crate::parse_timezone_spec("+09:00").unwrap();
         assert_eq!(
-            parse_input(Some("03 Feb 2020 01:03:10.534"), None, Some(tz)),
+            parse_input_instant(
+                Some("03 Feb 2020 01:03:10.534"),
+                &ParseOptions {
+                    input_timezone: Some(tz),
+                    ..ParseOptions::default()
+                }
+            ),
             expected_from_millis(1580691790534 - 9 * 3600 * 1000),
         );
     }
@@ -604,7 +1363,14 @@ mod tests {
     fn dateparser_path_with_input_tz() {
         // "May 23, 2020 12:00" + America/New_York (EDT = UTC-4) → 2020-05-23T16:00:00Z
         let tz = crate::parse_timezone_spec("America/New_York").unwrap();
-        let result = parse_input(Some("May 23, 2020 12:00"), None, Some(tz)).unwrap();
+        let result = parse_input_instant(
+            Some("May 23, 2020 12:00"),
+            &ParseOptions {
+                input_timezone: Some(tz),
+                ..ParseOptions::default()
+            },
+        )
+        .unwrap();
         assert_eq!(result.timestamp_millis(), 1590249600000);
     }
 
@@ -613,7 +1379,13 @@ mod tests {
         // Input already carries its own offset — override must be ignored
         let tz = crate::parse_timezone_spec("Asia/Tokyo").unwrap();
         assert_eq!(
-            parse_input(Some("2019-10-27T15:03:19.747-07:00"), None, Some(tz)),
+            parse_input_instant(
+                Some("2019-10-27T15:03:19.747-07:00"),
+                &ParseOptions {
+                    input_timezone: Some(tz),
+                    ..ParseOptions::default()
+                }
+            ),
             expected_from_millis(1572213799747),
         );
     }
@@ -622,8 +1394,821 @@ mod tests {
     fn input_tz_does_not_affect_epoch_input() {
         let tz = crate::parse_timezone_spec("Asia/Tokyo").unwrap();
         assert_eq!(
-            parse_input(Some("1572213799747"), None, Some(tz)),
+            parse_input_instant(
+                Some("1572213799747"),
+                &ParseOptions {
+                    input_timezone: Some(tz),
+                    ..ParseOptions::default()
+                }
+            ),
             expected_from_millis(1572213799747),
         );
     }
+
+    // 0x5DE2DD5C = 1575148892 → 2019-11-30T21:21:32 UTC
+    #[test]
+    fn epoch_hex_input() {
+        assert_eq!(
+            parse_input_instant(Some("0x5DE2DD5C"), &ParseOptions::default()),
+            expected_from_millis(1575148892000),
+        );
+    }
+
+    #[test]
+    fn epoch_hex_input_lowercase_prefix_and_digits() {
+        assert_eq!(
+            parse_input_instant(Some("0x5de2dd5c"), &ParseOptions::default()),
+            expected_from_millis(1575148892000),
+        );
+    }
+
+    // Hex millis are detected by magnitude like decimal input: 0x16EBE32A6DA = 1575149020890
+    #[test]
+    fn epoch_hex_input_auto_unit_millis() {
+        assert_eq!(
+            parse_input_instant(Some("0x16EBE32A6DA"), &ParseOptions::default()),
+            expected_from_millis(1575149020890),
+        );
+    }
+
+    #[test]
+    fn epoch_octal_input() {
+        assert_eq!(
+            parse_input_instant(Some("0o13570556534"), &ParseOptions::default()),
+            expected_from_millis(1575148892000),
+        );
+    }
+
+    #[test]
+    fn epoch_binary_input() {
+        assert_eq!(
+            parse_input_instant(
+                Some("0b1011101111000101101110101011100"),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1575148892000),
+        );
+    }
+
+    #[test]
+    fn epoch_negative_hex_input() {
+        assert_eq!(
+            parse_input_instant(Some("-0x10"), &ParseOptions::default()),
+            expected_from_millis(-16000),
+        );
+    }
+
+    #[test]
+    fn epoch_hex_with_forced_unit() {
+        assert_eq!(
+            parse_input_instant(
+                Some("0x3E8"),
+                &ParseOptions {
+                    epoch_unit: Some(EpochUnit::Millis),
+                    ..ParseOptions::default()
+                }
+            ),
+            expected_from_millis(1000),
+        );
+    }
+
+    #[test]
+    fn epoch_invalid_hex_digits_not_recognized() {
+        assert_eq!(
+            parse_input_instant(Some("0xZZ"), &ParseOptions::default()),
+            Err("Input format not recognized"),
+        );
+    }
+
+    #[test]
+    fn byte_order_le_4_byte_dump() {
+        assert_eq!(
+            parse_input_instant(
+                Some("5c dd e2 5d"),
+                &ParseOptions {
+                    byte_order: Some(ByteOrder::Le),
+                    ..ParseOptions::default()
+                }
+            ),
+            expected_from_millis(1575148892000),
+        );
+    }
+
+    #[test]
+    fn byte_order_le_4_byte_dump_with_prefix() {
+        assert_eq!(
+            parse_input_instant(
+                Some("0x5cdde25d"),
+                &ParseOptions {
+                    byte_order: Some(ByteOrder::Le),
+                    ..ParseOptions::default()
+                }
+            ),
+            expected_from_millis(1575148892000),
+        );
+    }
+
+    #[test]
+    fn byte_order_be_4_byte_dump() {
+        assert_eq!(
+            parse_input_instant(
+                Some("5d:e2:dd:5c"),
+                &ParseOptions {
+                    byte_order: Some(ByteOrder::Be),
+                    ..ParseOptions::default()
+                }
+            ),
+            expected_from_millis(1575148892000),
+        );
+    }
+
+    // 1575149020890 millis as little-endian i64: 0x0000016EBE32A6DA
+    #[test]
+    fn byte_order_le_8_byte_dump_auto_unit() {
+        assert_eq!(
+            parse_input_instant(
+                Some("da a6 32 be 6e 01 00 00"),
+                &ParseOptions {
+                    byte_order: Some(ByteOrder::Le),
+                    ..ParseOptions::default()
+                }
+            ),
+            expected_from_millis(1575149020890),
+        );
+    }
+
+    #[test]
+    fn byte_order_rejects_wrong_length() {
+        assert_eq!(
+            parse_input_instant(
+                Some("5c dd e2"),
+                &ParseOptions {
+                    byte_order: Some(ByteOrder::Le),
+                    ..ParseOptions::default()
+                }
+            ),
+            Err("--byte-order requires a 4 or 8 byte hex input"),
+        );
+    }
+
+    #[test]
+    fn arithmetic_add_iso_duration() {
+        assert_eq!(
+            parse_input_instant(
+                Some("2019-11-30T21:23:40.890Z + PT1H30M"),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1575149020890 + 90 * 60 * 1000),
+        );
+    }
+
+    #[test]
+    fn arithmetic_subtract_humantime_duration() {
+        assert_eq!(
+            parse_input_instant(Some("1575149020890 - 1day 2h"), &ParseOptions::default()),
+            expected_from_millis(1575149020890 - 26 * 3600 * 1000),
+        );
+    }
+
+    #[test]
+    fn arithmetic_calendar_month_uses_input_timezone() {
+        // 2019-10-31 12:00 in Los Angeles is PDT; a month later is 2019-11-30 12:00 PST
+        let tz = crate::parse_timezone_spec("America/Los_Angeles").unwrap();
+        assert_eq!(
+            parse_input_instant(
+                Some("2019-10-31T12:00:00 + P1M"),
+                &ParseOptions {
+                    input_timezone: Some(tz),
+                    ..ParseOptions::default()
+                }
+            ),
+            expected_from_millis(1575144000000),
+        );
+    }
+
+    #[test]
+    fn arithmetic_relative_to_now() {
+        let now = Utc.timestamp_millis_opt(1575149020890).unwrap();
+        assert_eq!(
+            parse_input(Some("now - PT1H"), &now, &ParseOptions::default())
+                .map(|parsed| parsed.instant),
+            expected_from_millis(1575145420890),
+        );
+    }
+
+    #[test]
+    fn relative_phrase_in_input_timezone() {
+        // 2019-11-30T21:23:40.890Z is a Saturday
+        let now = Utc.timestamp_millis_opt(1575149020890).unwrap();
+        let options = ParseOptions {
+            input_timezone: Some(TimeZoneSpec::Named(chrono_tz::America::New_York)),
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_input(Some("last tuesday 14:00"), &now, &options).map(|parsed| parsed.instant),
+            Ok(DateTime::parse_from_rfc3339("2019-11-26T14:00:00-05:00")
+                .unwrap()
+                .to_utc()),
+        );
+        assert_eq!(
+            parse_input(Some("yesterday + PT9H"), &now, &options).map(|parsed| parsed.instant),
+            Ok(DateTime::parse_from_rfc3339("2019-11-29T09:00:00-05:00")
+                .unwrap()
+                .to_utc()),
+        );
+    }
+
+    #[test]
+    fn arithmetic_with_business_days() {
+        // 2020-12-24 is a Thursday, the weekend is Friday and Saturday and Sunday the 27th is a
+        // holiday
+        let options = ParseOptions {
+            business_calendar: crate::BusinessCalendar {
+                weekend: vec![Weekday::Fri, Weekday::Sat],
+                holidays: [NaiveDate::from_ymd_opt(2020, 12, 27).unwrap()].into(),
+            },
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_input_instant(Some("2020-12-24T09:00:00Z + 1bd"), &options),
+            expected_from_millis(1609146000000),
+        );
+        assert_eq!(
+            parse_input_instant(Some("2020-12-28T09:00:00Z - 1 bd"), &options),
+            expected_from_millis(1608800400000),
+        );
+        assert_eq!(
+            parse_input_instant(Some("2020-12-24T09:00:00Z +1bd"), &options),
+            expected_from_millis(1609146000000),
+        );
+        assert_eq!(
+            parse_input_instant(Some("2020-12-28T09:00:00Z-1bd"), &options),
+            expected_from_millis(1608800400000),
+        );
+    }
+
+    #[test]
+    fn arithmetic_offset_follows_input_timezone_across_dst() {
+        let options = ParseOptions {
+            input_timezone: Some(TimeZoneSpec::Named(chrono_tz::America::Los_Angeles)),
+            ..ParseOptions::default()
+        };
+        let pdt = FixedOffset::west_opt(7 * 3600).unwrap();
+        let pst = FixedOffset::west_opt(8 * 3600).unwrap();
+        let parsed = parse_input(Some("2019-11-02T12:00:00 + P1D"), &Utc::now(), &options).unwrap();
+        assert_eq!(parsed.instant, expected_from_millis(1572811200000).unwrap());
+        assert_eq!(parsed.offset, pst);
+        let parsed =
+            parse_input(Some("2019-11-03T12:00:00 - 1 day"), &Utc::now(), &options).unwrap();
+        assert_eq!(parsed.offset, pdt);
+        // An offset written in the input stays as it was
+        let parsed = parse_input(
+            Some("2019-11-02T12:00:00+02:00 + P1D"),
+            &Utc::now(),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(parsed.offset, FixedOffset::east_opt(2 * 3600).unwrap());
+    }
+
+    #[test]
+    fn arithmetic_requires_spaced_operator() {
+        // The `-07:00` offset must not be mistaken for subtraction
+        assert_eq!(
+            parse_input_instant(Some("2019-10-27T15:03:19-07:00"), &ParseOptions::default()),
+            expected_from_millis(1572213799000),
+        );
+    }
+
+    #[test]
+    fn arithmetic_invalid_base() {
+        assert_eq!(
+            parse_input_instant(Some("not a date + PT1H"), &ParseOptions::default()),
+            Err("Input format not recognized"),
+        );
+    }
+
+    #[test]
+    fn precision_from_epoch_unit() {
+        let parsed = parse_input(
+            Some("1572213799747000"),
+            &Utc::now(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(parsed.precision, Precision::Micros);
+    }
+
+    #[test]
+    fn precision_from_text_digits() {
+        for (input, precision) in [
+            ("2019-10-27T22:03:19Z", Precision::Secs),
+            ("2019-10-27T22:03:19.7Z", Precision::Millis),
+            ("2019-10-27T22:03:19.747123Z", Precision::Micros),
+            ("2019-10-27 22:03:19,747", Precision::Millis),
+            ("27/Oct/2019:22:03:19 +0000", Precision::Secs),
+        ] {
+            let parsed = parse_input(Some(input), &Utc::now(), &ParseOptions::default()).unwrap();
+            assert_eq!(parsed.precision, precision, "{input}");
+        }
+    }
+
+    #[test]
+    fn nanos_restored_beyond_speedate() {
+        let parsed = parse_input(
+            Some("2019-10-27T22:03:19.747123456Z"),
+            &Utc::now(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(parsed.precision, Precision::Nanos);
+        assert_eq!(parsed.instant.timestamp_subsec_nanos(), 747_123_456);
+    }
+
+    #[test]
+    fn bare_time_anchored_to_today_in_input_timezone() {
+        // Already 2019-12-01 in Tokyo
+        let now = Utc.timestamp_millis_opt(1575149020890).unwrap();
+        let options = ParseOptions {
+            input_timezone: Some(TimeZoneSpec::Named(chrono_tz::Asia::Tokyo)),
+            ..ParseOptions::default()
+        };
+        let parsed = parse_input(Some("14:30"), &now, &options).unwrap();
+        assert_eq!(
+            parsed.instant,
+            DateTime::parse_from_rfc3339("2019-12-01T14:30:00+09:00")
+                .unwrap()
+                .to_utc()
+        );
+        assert_eq!(
+            parsed.anchor,
+            Some(Anchor::Date {
+                date: NaiveDate::from_ymd_opt(2019, 12, 1).unwrap(),
+                explicit: false,
+            })
+        );
+    }
+
+    #[test]
+    fn bare_time_with_explicit_anchor() {
+        let now = Utc.timestamp_millis_opt(1575149020890).unwrap();
+        let options = ParseOptions {
+            anchor: NaiveDate::from_ymd_opt(2020, 3, 1),
+            ..ParseOptions::default()
+        };
+        let parsed = parse_input(Some("09:15:00.250"), &now, &options).unwrap();
+        assert_eq!(parsed.instant.timestamp_millis(), 1583054100250);
+        assert_eq!(parsed.precision, Precision::Millis);
+        assert_eq!(
+            parse_input(Some("2pm"), &now, &options).map(|parsed| parsed.instant),
+            expected_from_millis(1583071200000),
+        );
+    }
+
+    #[test]
+    fn bare_date_start_and_end_of_day() {
+        let now = Utc.timestamp_millis_opt(1575149020890).unwrap();
+        let options = ParseOptions {
+            input_timezone: Some(TimeZoneSpec::Named(chrono_tz::America::New_York)),
+            ..ParseOptions::default()
+        };
+        let start = parse_input(Some("2020-03-01"), &now, &options).unwrap();
+        assert_eq!(start.instant.timestamp_millis(), 1583038800000);
+        assert_eq!(start.anchor, Some(Anchor::StartOfDay));
+
+        let end = parse_input(
+            Some("2020/03/01"),
+            &now,
+            &ParseOptions {
+                end_of_day: true,
+                ..options
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            end.instant,
+            DateTime::parse_from_rfc3339("2020-03-01T23:59:59.999999999-05:00")
+                .unwrap()
+                .to_utc()
+        );
+        assert_eq!(end.anchor, Some(Anchor::EndOfDay));
+    }
+
+    #[test]
+    fn describe_applied_anchor() {
+        assert_eq!(
+            describe_anchor(
+                Some(Anchor::Date {
+                    date: NaiveDate::from_ymd_opt(2020, 3, 1).unwrap(),
+                    explicit: true,
+                }),
+                None
+            ),
+            "Time of day anchored to 2020-03-01 from --anchor, in UTC"
+        );
+        assert_eq!(
+            describe_anchor(
+                Some(Anchor::StartOfDay),
+                Some(TimeZoneSpec::Named(chrono_tz::America::New_York))
+            ),
+            "Date anchored to the start of the day (00:00:00) in America/New_York"
+        );
+        assert_eq!(
+            describe_anchor(None, None),
+            "No anchor applied, the input names a complete instant"
+        );
+    }
+
+    fn parse_at_reference_now(
+        input: &str,
+        options: &ParseOptions,
+    ) -> Result<DateTime<Utc>, &'static str> {
+        // 2019-11-30T21:23:40.890Z
+        let now = Utc.timestamp_millis_opt(1575149020890).unwrap();
+        parse_input(Some(input), &now, options).map(|parsed| parsed.instant)
+    }
+
+    #[test]
+    fn syslog_infers_most_recent_year() {
+        assert_eq!(
+            parse_at_reference_now("Oct 27 22:03:19", &ParseOptions::default()),
+            expected_from_millis(1572213799000),
+        );
+        // Still in the future in 2019, so it must be from 2018
+        assert_eq!(
+            parse_at_reference_now("Dec  5 10:00:00.250", &ParseOptions::default()),
+            expected_from_millis(1544004000250),
+        );
+        // The most recent February 29th
+        assert_eq!(
+            parse_at_reference_now("Feb 29 12:00:00", &ParseOptions::default()),
+            expected_from_millis(1456747200000),
+        );
+    }
+
+    #[test]
+    fn syslog_with_year_and_input_timezone() {
+        let options = ParseOptions {
+            input_timezone: Some(TimeZoneSpec::Named(chrono_tz::America::Los_Angeles)),
+            year: Some(2017),
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_at_reference_now("Oct 27 15:03:19", &options),
+            expected_from_millis(1509141799000),
+        );
+    }
+
+    #[test]
+    fn syslog_across_dst_changes_keeps_the_year() {
+        let options = ParseOptions {
+            input_timezone: Some(TimeZoneSpec::Named(chrono_tz::America::Los_Angeles)),
+            ..ParseOptions::default()
+        };
+        // 01:30 happened twice on 2019-11-03; the first one is taken
+        assert_eq!(
+            parse_at_reference_now("Nov  3 01:30:00", &options),
+            expected_from_millis(1572769800000),
+        );
+        // 02:30 was skipped on 2019-03-10, so it moves forward to 03:00
+        assert_eq!(
+            parse_at_reference_now("Mar 10 02:30:00", &options),
+            expected_from_millis(1552212000000),
+        );
+    }
+
+    #[test]
+    fn ls_long_listing_dates() {
+        assert_eq!(
+            parse_at_reference_now("Nov 30 13:23", &ParseOptions::default()),
+            expected_from_millis(1575120180000),
+        );
+        assert_eq!(
+            parse_at_reference_now("Nov 30 21:30", &ParseOptions::default()),
+            expected_from_millis(1543613400000),
+        );
+        assert_eq!(
+            parse_at_reference_now("Nov 30  2018", &ParseOptions::default()),
+            expected_from_millis(1543536000000),
+        );
+    }
+
+    #[test]
+    fn date_command_with_zone_abbreviation() {
+        assert_eq!(
+            parse_input_instant(
+                Some("Sat Nov 30 13:23:40 PST 2019"),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1575149020000),
+        );
+        assert_eq!(
+            parse_input_instant(
+                Some("Sun Jul  5 09:00:00 CEST 2020"),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1593932400000),
+        );
+        assert_eq!(
+            parse_input_instant(
+                Some("Sat Nov 30 13:23:40 XYZ 2019"),
+                &ParseOptions::default()
+            ),
+            Err("Input format not recognized"),
+        );
+    }
+
+    #[test]
+    fn rfc5424_syslog_timestamp() {
+        let parsed = parse_input(
+            Some("2019-11-30T21:23:40.890123+00:00"),
+            &Utc::now(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(parsed.instant.timestamp_micros(), 1575149020890123);
+        assert_eq!(parsed.precision, Precision::Micros);
+        let parsed = parse_input(
+            Some("2019-11-30T13:23:40.890123-08:00"),
+            &Utc::now(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(parsed.instant.timestamp_micros(), 1575149020890123);
+        assert_eq!(parsed.offset, FixedOffset::west_opt(8 * 3600).unwrap());
+    }
+
+    #[test]
+    fn user_input_formats_are_tried_in_order() {
+        let options = ParseOptions {
+            input_timezone: Some(TimeZoneSpec::Named(chrono_tz::Europe::Paris)),
+            input_formats: vec![String::from("%d/%m/%Y %H:%M"), String::from("%d.%m.%Y")],
+            ..ParseOptions::default()
+        };
+        let parsed = parse_input(Some("01/07/2024 09:30"), &Utc::now(), &options).unwrap();
+        assert_eq!(parsed.instant.to_rfc3339(), "2024-07-01T07:30:00+00:00");
+        assert_eq!(parsed.offset, FixedOffset::east_opt(2 * 3600).unwrap());
+        let parsed = parse_input(Some("24.12.2024"), &Utc::now(), &options).unwrap();
+        assert_eq!(parsed.instant.to_rfc3339(), "2024-12-23T23:00:00+00:00");
+        let options = ParseOptions {
+            input_formats: vec![String::from("%d/%m/%Y %H:%M %z")],
+            ..ParseOptions::default()
+        };
+        let parsed = parse_input(Some("01/07/2024 09:30 -0500"), &Utc::now(), &options).unwrap();
+        assert_eq!(parsed.instant.to_rfc3339(), "2024-07-01T14:30:00+00:00");
+        assert_eq!(parsed.offset, FixedOffset::west_opt(5 * 3600).unwrap());
+        let parsed = parse_input(Some("1575149020890"), &Utc::now(), &options).unwrap();
+        assert_eq!(parsed.instant.timestamp_millis(), 1575149020890);
+    }
+
+    #[test]
+    fn journald_realtime_timestamp() {
+        let parsed = parse_input(
+            Some("__REALTIME_TIMESTAMP=1575149020890123"),
+            &Utc::now(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(parsed.instant.timestamp_micros(), 1575149020890123);
+        assert_eq!(parsed.precision, Precision::Micros);
+        assert_eq!(
+            parse_input_instant(Some("__REALTIME_TIMESTAMP=abc"), &ParseOptions::default()),
+            Err("Input format not recognized"),
+        );
+    }
+
+    #[test]
+    fn klog_header_infers_year() {
+        let parsed = parse_input(
+            Some("I1130 21:23:40.890123    7 main.go:42] Starting"),
+            &Utc.timestamp_millis_opt(1575149021000).unwrap(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(parsed.instant.timestamp_micros(), 1575149020890123);
+        assert_eq!(parsed.precision, Precision::Micros);
+        assert_eq!(
+            parse_at_reference_now(
+                "E1201 08:00:00.000000",
+                &ParseOptions {
+                    year: Some(2019),
+                    ..ParseOptions::default()
+                }
+            ),
+            expected_from_millis(1575187200000),
+        );
+        assert_eq!(
+            parse_at_reference_now("X1130 21:23:40.890123", &ParseOptions::default()),
+            Err("Input format not recognized"),
+        );
+    }
+
+    #[test]
+    fn go_time_string_with_monotonic_reading() {
+        assert_eq!(
+            parse_input_instant(
+                Some("2019-11-30 21:23:40.89 +0000 UTC m=+0.000123"),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1575149020890),
+        );
+        assert_eq!(
+            parse_input_instant(
+                Some("2019-11-30 13:23:40.89 -0800 PST"),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1575149020890),
+        );
+    }
+
+    #[test]
+    fn python_datetime_repr() {
+        let parsed = parse_input(
+            Some(
+                "datetime.datetime(2019, 11, 30, 21, 23, 40, 890000, tzinfo=datetime.timezone.utc)",
+            ),
+            &Utc::now(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(parsed.instant.timestamp_millis(), 1575149020890);
+        assert_eq!(parsed.precision, Precision::Micros);
+        assert_eq!(
+            parse_input_instant(
+                Some(
+                    "datetime.datetime(2019, 11, 30, 13, 23, 40, tzinfo=zoneinfo.ZoneInfo(key='America/Los_Angeles'))"
+                ),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1575149020000),
+        );
+        assert_eq!(
+            parse_input_instant(
+                Some(
+                    "datetime.datetime(2019, 11, 30, 13, 23, 40, tzinfo=datetime.timezone(datetime.timedelta(days=-1, seconds=57600)))"
+                ),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1575149020000),
+        );
+        assert_eq!(
+            parse_input_instant(
+                Some("datetime(2019, 11, 30, 21, 23)"),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1575148980000),
+        );
+    }
+
+    #[test]
+    fn excel_serial_dates() {
+        let excel = |system| ParseOptions {
+            excel: Some(system),
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_input_instant(Some("43799.891445486111"), &excel(ExcelSystem::From1900)),
+            expected_from_millis(1575149020890),
+        );
+        assert_eq!(
+            parse_input_instant(Some("42337.891445486111"), &excel(ExcelSystem::From1904)),
+            expected_from_millis(1575149020890),
+        );
+        // Either side of the 1900-02-29 that Excel counts but never happened
+        assert_eq!(
+            parse_input_instant(Some("59"), &excel(ExcelSystem::From1900)),
+            Ok(Utc.with_ymd_and_hms(1900, 2, 28, 0, 0, 0).unwrap()),
+        );
+        assert_eq!(
+            parse_input_instant(Some("61"), &excel(ExcelSystem::From1900)),
+            Ok(Utc.with_ymd_and_hms(1900, 3, 1, 0, 0, 0).unwrap()),
+        );
+        assert!(parse_input_instant(Some("60"), &excel(ExcelSystem::From1900)).is_err());
+        assert!(parse_input_instant(Some("-1"), &excel(ExcelSystem::From1904)).is_err());
+    }
+
+    #[test]
+    fn python_repr_with_positional_timedelta() {
+        assert_eq!(
+            parse_input_instant(
+                Some(
+                    "datetime.datetime(2019, 11, 30, 21, 23, 40, tzinfo=datetime.timezone(datetime.timedelta(0, 3600)))"
+                ),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1575145420000),
+        );
+        assert_eq!(
+            parse_input_instant(
+                Some(
+                    "datetime.datetime(2019, 11, 30, 21, 23, 40, tzinfo=datetime.timezone(datetime.timedelta(-1, 82800, minutes=30)))"
+                ),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1575149020000 + 1800 * 1000),
+        );
+        assert!(
+            parse_input(
+                Some(
+                    "datetime.datetime(2019, 11, 30, 21, 23, 40, tzinfo=datetime.timezone(datetime.timedelta(0, 3600, 5)))"
+                ),
+                &Utc::now(),
+                &ParseOptions::default()
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn python_repr_rejects_out_of_range_timedelta() {
+        assert!(
+            parse_input(
+                Some(
+                    "datetime.datetime(2019, 11, 30, 13, 23, 40, tzinfo=datetime.timezone(datetime.timedelta(days=30000)))"
+                ),
+                &Utc::now(),
+                &ParseOptions::default(),
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn java_zoned_date_time_sets_display_zone() {
+        let parsed = parse_input(
+            Some("2019-11-30T13:23:40.890-08:00[America/Los_Angeles]"),
+            &Utc::now(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(parsed.instant.timestamp_millis(), 1575149020890);
+        assert_eq!(
+            parsed.zone,
+            Some(TimeZoneSpec::Named(chrono_tz::America::Los_Angeles))
+        );
+        assert_eq!(
+            parse_input_instant(
+                Some("2019-11-30T21:23:40.890Z[UTC]"),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1575149020890),
+        );
+    }
+
+    #[test]
+    fn ruby_time_to_s() {
+        assert_eq!(
+            parse_input_instant(Some("2019-11-30 13:23:40 -0800"), &ParseOptions::default()),
+            expected_from_millis(1575149020000),
+        );
+    }
+
+    #[test]
+    fn sql_timestamp_literal() {
+        assert_eq!(
+            parse_input_instant(
+                Some("TIMESTAMP '2019-11-30 21:23:40'"),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1575149020000),
+        );
+        assert_eq!(
+            parse_input_instant(
+                Some("timestamp with time zone '2019-11-30 13:23:40-08'"),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1575149020000),
+        );
+    }
+
+    #[test]
+    fn powershell_long_date() {
+        assert_eq!(
+            parse_input_instant(
+                Some("Saturday, November 30, 2019 9:23:40 PM"),
+                &ParseOptions::default()
+            ),
+            expected_from_millis(1575149020000),
+        );
+    }
+
+    #[test]
+    fn keeps_input_offset() {
+        let offset = |input: &str, options: &ParseOptions| {
+            parse_input(Some(input), &Utc::now(), options)
+                .unwrap()
+                .offset
+                .to_string()
+        };
+        let default = ParseOptions::default();
+        assert_eq!(offset("2019-11-30T13:27:45-08:00", &default), "-08:00");
+        assert_eq!(offset("27/Oct/2019:22:03:19 +0530", &default), "+05:30");
+        assert_eq!(offset("1575149020890", &default), "+00:00");
+        let tokyo = ParseOptions {
+            input_timezone: Some(crate::parse_timezone_spec("Asia/Tokyo").unwrap()),
+            ..ParseOptions::default()
+        };
+        assert_eq!(offset("2019-11-30 16:23:40", &tokyo), "+09:00");
+        assert_eq!(offset("2019-11-30T16:23:40Z", &tokyo), "+00:00");
+    }
 }
@@ -0,0 +1,138 @@
+use crate::TimeZoneSpec;
+use crate::duration::CalendarDuration;
+use chrono::prelude::*;
+
+/// Yields every instant from `start` towards `end` (inclusive) in multiples of `step`. Each one is
+/// computed from `start` rather than the previous element, so stepping a month from January 31st
+/// gives February 29th and then March 31st. Calendar parts of `step` follow `timezone`.
+///
+/// Fails when `step` doesn't move from `start` towards `end`.
+pub fn instants(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    step: CalendarDuration,
+    timezone: Option<TimeZoneSpec>,
+) -> Result<impl Iterator<Item = DateTime<Utc>>, &'static str> {
+    let ascending = end >= start;
+    let first_step = step
+        .add_to(&start, timezone)
+        .ok_or("Range step is out of range")?;
+    if first_step == start || (start != end && (first_step > start) != ascending) {
+        return Err("--step must move from the start towards the end");
+    }
+
+    Ok((0..=i32::MAX).map_while(move |i| {
+        let instant = step.checked_mul(i)?.add_to(&start, timezone)?;
+        let in_range = if ascending {
+            instant <= end
+        } else {
+            instant >= end
+        };
+        in_range.then_some(instant)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_calendar_duration;
+
+    fn datetime(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().to_utc()
+    }
+
+    fn range(start: &str, end: &str, step: &str, timezone: Option<&str>) -> Vec<DateTime<Utc>> {
+        instants(
+            datetime(start),
+            datetime(end),
+            parse_calendar_duration(step).unwrap(),
+            timezone.map(|tz| crate::parse_timezone_spec(tz).unwrap()),
+        )
+        .unwrap()
+        .collect()
+    }
+
+    #[test]
+    fn hourly_inclusive() {
+        assert_eq!(
+            range("2019-11-30T21:00:00Z", "2019-11-30T23:00:00Z", "1h", None),
+            vec![
+                datetime("2019-11-30T21:00:00Z"),
+                datetime("2019-11-30T22:00:00Z"),
+                datetime("2019-11-30T23:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_from_month_end() {
+        assert_eq!(
+            range(
+                "2020-01-31T00:00:00Z",
+                "2020-04-15T00:00:00Z",
+                "1 month",
+                None
+            ),
+            vec![
+                datetime("2020-01-31T00:00:00Z"),
+                datetime("2020-02-29T00:00:00Z"),
+                datetime("2020-03-31T00:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn daily_across_dst_keeps_local_midnight() {
+        assert_eq!(
+            range(
+                "2019-11-02T00:00:00-07:00",
+                "2019-11-04T00:00:00-08:00",
+                "P1D",
+                Some("America/Los_Angeles")
+            ),
+            vec![
+                datetime("2019-11-02T00:00:00-07:00"),
+                datetime("2019-11-03T00:00:00-07:00"),
+                datetime("2019-11-04T00:00:00-08:00"),
+            ]
+        );
+    }
+
+    #[test]
+    fn descending_with_negative_step() {
+        assert_eq!(
+            range(
+                "2019-11-30T23:00:00Z",
+                "2019-11-30T22:00:00Z",
+                "-PT30M",
+                None
+            ),
+            vec![
+                datetime("2019-11-30T23:00:00Z"),
+                datetime("2019-11-30T22:30:00Z"),
+                datetime("2019-11-30T22:00:00Z"),
+            ]
+        );
+        assert_eq!(
+            range("2019-11-30T23:00:00Z", "2019-11-30T21:00:00Z", "-1h", None),
+            vec![
+                datetime("2019-11-30T23:00:00Z"),
+                datetime("2019-11-30T22:00:00Z"),
+                datetime("2019-11-30T21:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn step_away_from_end_is_an_error() {
+        assert!(
+            instants(
+                datetime("2019-11-30T23:00:00Z"),
+                datetime("2019-11-30T22:00:00Z"),
+                parse_calendar_duration("1h").unwrap(),
+                None,
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use super::converting::{self, ConversionResult};
+use chrono::prelude::*;
+use serde::Serialize;
+use serde::ser::{SerializeMap, Serializer};
+
+/// What became of one input: the instant it was parsed as and its outputs keyed like the Alfred
+/// `uid`s, or the error that stopped it.
+#[derive(Serialize)]
+pub struct Record<'a> {
+    input: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parsed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    results: Option<Results<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
+
+impl<'a> Record<'a> {
+    pub fn converted(
+        input: &'a str,
+        instant: &DateTime<Utc>,
+        conversion_results: &'a [ConversionResult],
+    ) -> Self {
+        Record {
+            input,
+            parsed: Some(instant.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+            results: Some(Results(conversion_results)),
+            error: None,
+        }
+    }
+
+    pub fn failed(input: &'a str, error: &'a str) -> Self {
+        Record {
+            input,
+            parsed: None,
+            results: None,
+            error: Some(error),
+        }
+    }
+}
+
+// Serializes as a map in output order, keyed by `converting::unique_keys` so that every format can
+// represent a key that repeats.
+struct Results<'a>(&'a [ConversionResult]);
+
+impl Serialize for Results<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, conversion_result) in converting::unique_keys(self.0).iter().zip(self.0) {
+            map.serialize_entry(key, &conversion_result.converted_text)?;
+        }
+        map.end()
+    }
+}
+
+/// One JSON object on a single line.
+pub fn to_ndjson(record: &Record) -> String {
+    serde_json::to_string(record).unwrap()
+}
+
+/// One item of a YAML sequence, so the records of several inputs concatenate into a list.
+pub fn to_yaml(record: &Record) -> String {
+    serde_norway::to_string(&[record]).unwrap()
+}
+
+/// One `[[records]]` table, so the records of several inputs concatenate into an array of tables.
+pub fn to_toml(record: &Record) -> String {
+    #[derive(Serialize)]
+    struct Records<'a, 'b> {
+        records: [&'b Record<'a>; 1],
+    }
+
+    toml::to_string(&Records { records: [record] }).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OutputFormat;
+
+    fn results() -> Vec<ConversionResult> {
+        vec![
+            ConversionResult {
+                converted_text: String::from("2019-11-30T21:23:40.890Z"),
+                format: OutputFormat::Utc,
+            },
+            ConversionResult {
+                converted_text: String::from("1575149020890"),
+                format: OutputFormat::Millis,
+            },
+            ConversionResult {
+                converted_text: String::from("30/11"),
+                format: OutputFormat::Strftime(String::from("%d/%m")),
+            },
+            ConversionResult {
+                converted_text: String::from("2019"),
+                format: OutputFormat::Strftime(String::from("%Y")),
+            },
+        ]
+    }
+
+    fn instant() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2019-11-30T21:23:40.890Z")
+            .unwrap()
+            .to_utc()
+    }
+
+    #[test]
+    fn ndjson_records() {
+        let results = results();
+        assert_eq!(
+            to_ndjson(&Record::converted("1575149020890", &instant(), &results)),
+            r#"{"input":"1575149020890","parsed":"2019-11-30T21:23:40.890Z","results":{"utc":"2019-11-30T21:23:40.890Z","millis":"1575149020890","strftime":"30/11","strftime_2":"2019"}}"#
+        );
+        assert_eq!(
+            to_ndjson(&Record::failed("nonsense", "Could not parse input")),
+            r#"{"input":"nonsense","error":"Could not parse input"}"#
+        );
+    }
+
+    #[test]
+    fn yaml_records() {
+        let results = results();
+        assert_eq!(
+            to_yaml(&Record::converted(
+                "1575149020890",
+                &instant(),
+                &results[..2]
+            )),
+            "- input: '1575149020890'\n  parsed: 2019-11-30T21:23:40.890Z\n  results:\n    utc: 2019-11-30T21:23:40.890Z\n    millis: '1575149020890'\n"
+        );
+        assert_eq!(
+            to_yaml(&Record::failed("nonsense", "Could not parse input")),
+            "- input: nonsense\n  error: Could not parse input\n"
+        );
+    }
+
+    #[test]
+    fn toml_records() {
+        let results = results();
+        assert_eq!(
+            to_toml(&Record::converted(
+                "1575149020890",
+                &instant(),
+                &results[..2]
+            )),
+            "[[records]]\ninput = \"1575149020890\"\nparsed = \"2019-11-30T21:23:40.890Z\"\n\n[records.results]\nutc = \"2019-11-30T21:23:40.890Z\"\nmillis = \"1575149020890\"\n"
+        );
+        assert_eq!(
+            to_toml(&Record::failed("nonsense", "Could not parse input")),
+            "[[records]]\ninput = \"nonsense\"\nerror = \"Could not parse input\"\n"
+        );
+    }
+}
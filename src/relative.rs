@@ -0,0 +1,292 @@
+use crate::duration::{self, CalendarDuration};
+use crate::parsing::{self, ParsedInput};
+use crate::{Precision, TimeZoneSpec, snapping};
+use chrono::Days;
+use chrono::prelude::*;
+
+/// Resolves a relative phrase against `now`, with days following the calendar of `timezone`
+/// (UTC when `None`). Recognised phrases are:
+///
+/// - `now`
+/// - `<duration> ago` and `in <duration>`, e.g. `3 days ago`, `in 1h 30m`, `an hour ago`
+/// - `last`/`next` followed by `day`, `week`, `month` or `year`
+/// - `today`, `yesterday`, `tomorrow`, `[last|next|this] <weekday>`, optionally followed by a time
+///   of day such as `9am`, `at 14:00`, `noon` or `midnight`
+///
+/// Phrases naming a day resolve to local midnight unless a time is given. `last monday` is the
+/// most recent Monday before today and `next friday` the first Friday after today, while
+/// `this friday` (or just `friday`) may also be today.
+pub fn parse_relative(
+    input: &str,
+    now: &DateTime<Utc>,
+    timezone: Option<TimeZoneSpec>,
+) -> Option<ParsedInput> {
+    let words: Vec<&str> = input
+        .split_whitespace()
+        .filter(|word| !word.eq_ignore_ascii_case("at"))
+        .collect();
+    let keyword = |index: usize, expected: &str| {
+        words
+            .get(index)
+            .is_some_and(|word| word.eq_ignore_ascii_case(expected))
+    };
+    let from_now = |instant| {
+        Some(ParsedInput {
+            instant,
+            precision: Precision::Millis,
+            anchor: None,
+            zone: None,
+            offset: parsing::offset_in(timezone, &instant),
+        })
+    };
+    match words.len() {
+        0 => None,
+        1 if keyword(0, "now") => from_now(*now),
+        len if keyword(len - 1, "ago") => (-parse_amount(&words[..len - 1])?)
+            .add_to(now, timezone)
+            .and_then(from_now),
+        _ if keyword(0, "in") => parse_amount(&words[1..])?
+            .add_to(now, timezone)
+            .and_then(from_now),
+        2 if keyword(0, "last") || keyword(0, "next") => match relative_unit(words[1]) {
+            Some(step) => {
+                let step = if keyword(0, "last") { -step } else { step };
+                step.add_to(now, timezone).and_then(from_now)
+            }
+            None => parse_day_and_time(&words, now, timezone),
+        },
+        _ => parse_day_and_time(&words, now, timezone),
+    }
+}
+
+// Parses the duration in `3 days ago` or `in an hour`, where a leading `a`/`an` stands for one.
+fn parse_amount(words: &[&str]) -> Option<CalendarDuration> {
+    let text = match words {
+        [article, rest @ ..]
+            if article.eq_ignore_ascii_case("a") || article.eq_ignore_ascii_case("an") =>
+        {
+            format!("1 {}", rest.join(" "))
+        }
+        _ => words.join(" "),
+    };
+    duration::parse_calendar_duration(&text)
+}
+
+fn relative_unit(word: &str) -> Option<CalendarDuration> {
+    let mut step = CalendarDuration::default();
+    match word.to_ascii_lowercase().as_str() {
+        "day" => step.days = 1,
+        "week" => step.days = 7,
+        "month" => step.months = 1,
+        "year" => step.months = 12,
+        _ => return None,
+    }
+    Some(step)
+}
+
+fn parse_day_and_time(
+    words: &[&str],
+    now: &DateTime<Utc>,
+    timezone: Option<TimeZoneSpec>,
+) -> Option<ParsedInput> {
+    // A time of day may be written as one word (`9am`) or as a number and a meridiem (`9 am`)
+    let (day_words, time) = match words {
+        [day @ .., hour, meridiem]
+            if meridiem.eq_ignore_ascii_case("am") || meridiem.eq_ignore_ascii_case("pm") =>
+        {
+            (day, Some(parse_time_of_day(&format!("{hour}{meridiem}"))?))
+        }
+        [day @ .., last] if !day.is_empty() => match parse_time_of_day(last) {
+            Some(time) => (day, Some(time)),
+            None => (words, None),
+        },
+        _ => (words, None),
+    };
+    let today = match timezone {
+        Some(tz) => tz.utc_to_naive(now),
+        None => now.naive_utc(),
+    }
+    .date();
+    let date = resolve_day(day_words, today)?;
+    let local = date.and_time(time.unwrap_or(NaiveTime::MIN));
+    snapping::to_utc(local, timezone).map(|instant| ParsedInput {
+        instant,
+        precision: Precision::Secs,
+        anchor: None,
+        zone: None,
+        offset: parsing::offset_in(timezone, &instant),
+    })
+}
+
+fn resolve_day(words: &[&str], today: NaiveDate) -> Option<NaiveDate> {
+    let lowercase: Vec<String> = words.iter().map(|w| w.to_ascii_lowercase()).collect();
+    let lowercase: Vec<&str> = lowercase.iter().map(String::as_str).collect();
+    match lowercase.as_slice() {
+        ["today"] => Some(today),
+        ["yesterday"] => today.checked_sub_days(Days::new(1)),
+        ["tomorrow"] => today.checked_add_days(Days::new(1)),
+        ["last", weekday] => {
+            let back = match today.weekday().days_since(weekday.parse().ok()?) {
+                0 => 7,
+                days => days,
+            };
+            today.checked_sub_days(Days::new(back.into()))
+        }
+        ["next", weekday] => {
+            let ahead = match weekday.parse::<Weekday>().ok()?.days_since(today.weekday()) {
+                0 => 7,
+                days => days,
+            };
+            today.checked_add_days(Days::new(ahead.into()))
+        }
+        ["this", weekday] | [weekday] => {
+            let ahead = weekday.parse::<Weekday>().ok()?.days_since(today.weekday());
+            today.checked_add_days(Days::new(ahead.into()))
+        }
+        _ => None,
+    }
+}
+
+// Accepts `9am`, `9:30pm`, `12am`, `14:00`, `14:00:30`, `noon` and `midnight`. A bare number is
+// not a time, so `next friday 3` is rejected rather than guessed.
+pub(crate) fn parse_time_of_day(word: &str) -> Option<NaiveTime> {
+    let word = word.to_ascii_lowercase();
+    match word.as_str() {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return Some(NaiveTime::MIN),
+        _ => {}
+    }
+    let (clock, pm) = match (word.strip_suffix("am"), word.strip_suffix("pm")) {
+        (Some(clock), _) => (clock, Some(false)),
+        (_, Some(clock)) => (clock, Some(true)),
+        _ => (word.as_str(), None),
+    };
+    let parts: Vec<u32> = clock
+        .split(':')
+        .map(|part| {
+            (!part.is_empty() && part.len() <= 2)
+                .then(|| part.parse().ok())
+                .flatten()
+        })
+        .collect::<Option<_>>()?;
+    let (hour, minute, second) = match (parts.as_slice(), pm) {
+        (&[hour], Some(_)) => (hour, 0, 0),
+        (&[hour, minute], _) => (hour, minute, 0),
+        (&[hour, minute, second], _) => (hour, minute, second),
+        _ => return None,
+    };
+    let hour = match pm {
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some(pm) => hour % 12 + if pm { 12 } else { 0 },
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    // 2019-11-30 is a Saturday
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2019-11-30T21:23:40.890Z")
+            .unwrap()
+            .to_utc()
+    }
+
+    fn datetime(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().to_utc()
+    }
+
+    fn relative(input: &str, timezone: Option<&str>) -> Option<DateTime<Utc>> {
+        parse_relative(
+            input,
+            &now(),
+            timezone.map(|tz| crate::parse_timezone_spec(tz).unwrap()),
+        )
+        .map(|parsed| parsed.instant)
+    }
+
+    #[test]
+    fn now_and_durations_from_now() {
+        assert_eq!(relative("now", None), Some(now()));
+        assert_eq!(
+            relative("3 days ago", None),
+            Some(now() - TimeDelta::days(3))
+        );
+        assert_eq!(
+            relative("in 1h 30m", None),
+            Some(now() + TimeDelta::minutes(90))
+        );
+        assert_eq!(
+            relative("an hour ago", None),
+            Some(now() - TimeDelta::hours(1))
+        );
+        assert_eq!(
+            relative("last month", None),
+            Some(datetime("2019-10-30T21:23:40.890Z"))
+        );
+    }
+
+    #[test]
+    fn named_days() {
+        assert_eq!(
+            relative("yesterday", None),
+            Some(datetime("2019-11-29T00:00:00Z"))
+        );
+        assert_eq!(
+            relative("Tomorrow at noon", None),
+            Some(datetime("2019-12-01T12:00:00Z"))
+        );
+        assert_eq!(
+            relative("today 9:30pm", None),
+            Some(datetime("2019-11-30T21:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(
+            relative("last monday 9am", None),
+            Some(datetime("2019-11-25T09:00:00Z"))
+        );
+        assert_eq!(
+            relative("last tuesday 14:00", None),
+            Some(datetime("2019-11-26T14:00:00Z"))
+        );
+        assert_eq!(
+            relative("next friday", None),
+            Some(datetime("2019-12-06T00:00:00Z"))
+        );
+        assert_eq!(
+            relative("last saturday", None),
+            Some(datetime("2019-11-23T00:00:00Z"))
+        );
+        assert_eq!(
+            relative("saturday 5 pm", None),
+            Some(datetime("2019-11-30T17:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn days_follow_input_timezone() {
+        // Already Sunday in Tokyo
+        assert_eq!(
+            relative("yesterday", Some("Asia/Tokyo")),
+            Some(datetime("2019-11-30T00:00:00+09:00"))
+        );
+        assert_eq!(
+            relative("last monday 9am", Some("America/Los_Angeles")),
+            Some(datetime("2019-11-25T09:00:00-08:00"))
+        );
+    }
+
+    #[test]
+    fn rejects_other_input() {
+        assert_eq!(relative("next friday 3", None), None);
+        assert_eq!(relative("last fortnight", None), None);
+        assert_eq!(relative("yesterday 13pm", None), None);
+        assert_eq!(relative("2019-11-30", None), None);
+    }
+}
@@ -0,0 +1,308 @@
+use crate::duration::{self, CalendarDuration};
+use crate::{ConvertOptions, OutputFormat, ParseOptions, Snap};
+use chrono::prelude::*;
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+use std::collections::BTreeMap;
+
+const HELP: &str = "\
+Enter a date-time to convert it, or an expression such as `$1 + 1h` or `$2 - $1`.
+:tz <zone>           set both the input and output timezone
+:input-tz <zone>     set the timezone assumed for inputs without one
+:output-tz <zone>    set the timezone of zoned outputs (`local` for the system zone)
+:outputs <list>      set the comma-separated outputs (no list restores the default)
+:now <input>         pin the reference time used for durations and relative inputs
+:now                 return to the live clock
+:help                show this message
+:quit                leave the prompt (or Ctrl-D)";
+
+/// The state carried between lines of a REPL session: the options given on the command line as
+/// changed by `:` commands, and every instant produced so far for `$1`-style references.
+pub struct Session {
+    parse_options: ParseOptions,
+    snap: Option<Snap>,
+    convert_options: ConvertOptions,
+    outputs: Vec<OutputFormat>,
+    /// Reference time for durations and relative inputs; the live clock when `None`.
+    now: Option<DateTime<Utc>>,
+    results: Vec<DateTime<Utc>>,
+    zone_aliases: BTreeMap<String, String>,
+}
+
+/// What the prompt should do after a line has been evaluated.
+#[derive(PartialEq, Eq, Debug)]
+pub enum Reply {
+    Print(Vec<String>),
+    Quit,
+}
+
+impl Session {
+    pub fn new(
+        parse_options: ParseOptions,
+        snap: Option<Snap>,
+        convert_options: ConvertOptions,
+        outputs: Vec<OutputFormat>,
+        zone_aliases: BTreeMap<String, String>,
+    ) -> Self {
+        Session {
+            parse_options,
+            snap,
+            convert_options,
+            outputs,
+            now: None,
+            results: Vec::new(),
+            zone_aliases,
+        }
+    }
+
+    // Names the result the next input will be stored as.
+    fn prompt(&self) -> String {
+        format!("${}> ", self.results.len() + 1)
+    }
+
+    /// Evaluates one line: a `:` command, an input to convert, or a difference of two inputs.
+    /// Converted inputs are numbered from `$1` in the order they were entered.
+    pub fn eval(&mut self, line: &str) -> Result<Reply, String> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(Reply::Print(Vec::new()));
+        }
+        if let Some(command) = line.strip_prefix(':') {
+            return self.command(command);
+        }
+        let input = self.expand_references(line)?;
+        let now = self.now.unwrap_or_else(Utc::now);
+        match crate::evaluate(
+            Some(&input),
+            &now,
+            &self.parse_options,
+            self.snap.as_ref(),
+            &self.convert_options,
+            &self.outputs,
+        ) {
+            Ok(evaluation) => {
+                self.results.push(evaluation.instant);
+                Ok(Reply::Print(
+                    evaluation
+                        .conversion_results
+                        .into_iter()
+                        .map(|result| result.converted_text)
+                        .collect(),
+                ))
+            }
+            Err(err) => self
+                .difference(&input, &now)
+                .map(Reply::Print)
+                .ok_or_else(|| String::from(err)),
+        }
+    }
+
+    fn command(&mut self, command: &str) -> Result<Reply, String> {
+        let (name, argument) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+        let argument = (!argument.is_empty()).then_some(argument);
+        match (name, argument) {
+            ("q" | "quit" | "exit", _) => return Ok(Reply::Quit),
+            ("help", _) => return Ok(Reply::Print(vec![String::from(HELP)])),
+            ("tz", Some(zone)) => {
+                let zone = crate::resolve_timezone(zone, &self.zone_aliases)?;
+                self.parse_options.input_timezone = Some(zone);
+                self.convert_options.display_tz = Some(zone);
+            }
+            ("input-tz", Some(zone)) => {
+                self.parse_options.input_timezone =
+                    Some(crate::resolve_timezone(zone, &self.zone_aliases)?);
+            }
+            ("output-tz", Some(zone)) => {
+                self.convert_options.display_tz = if zone.eq_ignore_ascii_case("local") {
+                    None
+                } else {
+                    Some(crate::resolve_timezone(zone, &self.zone_aliases)?)
+                };
+            }
+            ("tz" | "input-tz" | "output-tz", None) => {}
+            ("outputs", Some(list)) => {
+                self.outputs = list
+                    .split(',')
+                    .map(|output| crate::parse_output_format(output.trim()))
+                    .collect::<Result<_, _>>()?;
+            }
+            ("outputs", None) => self.outputs = crate::DEFAULT_OUTPUTS.to_vec(),
+            ("now", Some(input)) => {
+                let input = self.expand_references(input)?;
+                let now = self.now.unwrap_or_else(Utc::now);
+                self.now = Some(
+                    crate::parsing::parse_input(Some(&input), &now, &self.parse_options)?.instant,
+                );
+            }
+            ("now", None) => self.now = None,
+            _ => return Err(format!("Unknown command :{name}, see :help")),
+        }
+        if let Some(snap) = &mut self.snap {
+            snap.timezone = self
+                .convert_options
+                .display_tz
+                .or(self.parse_options.input_timezone);
+        }
+        Ok(Reply::Print(vec![self.describe()]))
+    }
+
+    // Summarises the session state after a command.
+    fn describe(&self) -> String {
+        let outputs: Vec<String> = self.outputs.iter().map(ToString::to_string).collect();
+        format!(
+            "input-tz {}, output-tz {}, outputs {}, now {}",
+            self.parse_options
+                .input_timezone
+                .map_or_else(|| String::from("UTC"), |zone| zone.to_string()),
+            self.convert_options
+                .display_tz
+                .map_or_else(|| String::from("local"), |zone| zone.to_string()),
+            outputs.join(","),
+            self.now.map_or_else(
+                || String::from("live"),
+                |now| now.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+            ),
+        )
+    }
+
+    // Replaces each `$N` with the UTC instant of the Nth result, keeping its full precision.
+    fn expand_references(&self, line: &str) -> Result<String, String> {
+        let mut expanded = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(pos) = rest.find('$') {
+            expanded.push_str(&rest[..pos]);
+            let after = &rest[pos + 1..];
+            let digits = after
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(after.len());
+            if digits == 0 {
+                expanded.push('$');
+            } else {
+                let reference = &after[..digits];
+                let instant = reference
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| self.results.get(n.checked_sub(1)?))
+                    .ok_or_else(|| format!("No result ${reference}"))?;
+                expanded.push_str(&instant.to_rfc3339_opts(SecondsFormat::AutoSi, true));
+            }
+            rest = &after[digits..];
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+
+    // Evaluates `<a> - <b>` where both sides are instants, as a human and an ISO 8601 duration.
+    fn difference(&self, input: &str, now: &DateTime<Utc>) -> Option<Vec<String>> {
+        let (end, start) = input.rsplit_once(" - ")?;
+        let parse = |side: &str| {
+            crate::parsing::parse_input(Some(side), now, &self.parse_options)
+                .ok()
+                .map(|parsed| parsed.instant)
+        };
+        let millis = (parse(end)? - parse(start)?).num_milliseconds();
+        let human = duration::format_human_millis(
+            millis.unsigned_abs(),
+            self.convert_options.duration_precision,
+            self.convert_options.duration_rounding,
+        );
+        Some(vec![
+            if millis < 0 {
+                format!("-{human}")
+            } else {
+                human
+            },
+            CalendarDuration::from_fixed_millis(millis).to_iso_string(),
+        ])
+    }
+}
+
+/// Reads lines until `:quit`, Ctrl-D or Ctrl-C, printing each reply or error.
+pub fn run(mut session: Session) -> Result<(), &'static str> {
+    let mut editor = DefaultEditor::new().map_err(|_| "Could not start line editing")?;
+    loop {
+        let line = match editor.readline(&session.prompt()) {
+            Ok(line) => line,
+            Err(ReadlineError::Eof | ReadlineError::Interrupted) => return Ok(()),
+            Err(_) => return Err("Could not read from the terminal"),
+        };
+        let _ = editor.add_history_entry(line.as_str());
+        match session.eval(&line) {
+            Ok(Reply::Print(lines)) => {
+                for line in lines {
+                    println!("{line}");
+                }
+            }
+            Ok(Reply::Quit) => return Ok(()),
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Session {
+        let mut session = Session::new(
+            ParseOptions::default(),
+            None,
+            ConvertOptions {
+                display_tz: Some(crate::parse_timezone_spec("UTC").unwrap()),
+                ..ConvertOptions::default()
+            },
+            vec![OutputFormat::Utc, OutputFormat::Millis],
+            BTreeMap::from([(String::from("dub"), String::from("Europe/Dublin"))]),
+        );
+        session.eval(":now 2019-11-30T21:23:40.890Z").unwrap();
+        session
+    }
+
+    fn lines(reply: Result<Reply, String>) -> Vec<String> {
+        match reply {
+            Ok(Reply::Print(lines)) => lines,
+            other => panic!("unexpected reply {other:?}"),
+        }
+    }
+
+    #[test]
+    fn references_earlier_results() {
+        let mut session = session();
+        assert_eq!(
+            lines(session.eval("1575149020890")),
+            vec!["2019-11-30T21:23:40.890Z", "1575149020890"]
+        );
+        assert_eq!(session.prompt(), "$2> ");
+        assert_eq!(
+            lines(session.eval("$1 + 1h 30m")),
+            vec!["2019-11-30T22:53:40.890Z", "1575154420890"]
+        );
+        assert_eq!(lines(session.eval("$1 - $2")), vec!["-1h 30m", "-PT1H30M"]);
+        assert_eq!(session.eval("$3 + 1h"), Err(String::from("No result $3")));
+    }
+
+    #[test]
+    fn commands_change_session_state() {
+        let mut session = session();
+        assert_eq!(
+            lines(session.eval(":tz dub")),
+            vec![
+                "input-tz Europe/Dublin, output-tz Europe/Dublin, outputs utc,millis, now 2019-11-30T21:23:40.890Z"
+            ]
+        );
+        session.eval(":outputs zoned,seconds:3").unwrap();
+        assert_eq!(
+            lines(session.eval("2019-07-01 12:00")),
+            vec!["2019-07-01T12:00:00.000+01:00", "1561978800.000"]
+        );
+        assert_eq!(
+            lines(session.eval("yesterday")),
+            vec!["2019-11-29T00:00:00.000Z", "1574985600.000"]
+        );
+        assert_eq!(session.eval(":quit"), Ok(Reply::Quit));
+        assert!(session.eval(":outputs fortnights").is_err());
+        assert!(session.eval(":frobnicate").is_err());
+    }
+}
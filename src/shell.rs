@@ -0,0 +1,129 @@
+use super::Shell;
+use super::converting::{self, ConversionResult};
+
+/// Renders one assignment per conversion, named after its uppercased key with `prefix` in front,
+/// e.g. `export TT_MILLIS='1575149020890'`. A repeated key is suffixed like in records
+/// (`TT_STRFTIME_2`).
+pub fn output(conversion_results: &[ConversionResult], shell: Shell, prefix: &str) -> Vec<String> {
+    converting::unique_keys(conversion_results)
+        .iter()
+        .zip(conversion_results)
+        .map(|(key, conversion_result)| {
+            let name = format!("{prefix}{}", key.to_ascii_uppercase());
+            assignment(shell, &name, &conversion_result.converted_text)
+        })
+        .collect()
+}
+
+/// Renders every row of a range, suffixing each name with its row index so they stay unique.
+pub fn output_rows(rows: &[Vec<ConversionResult>], shell: Shell, prefix: &str) -> Vec<String> {
+    rows.iter()
+        .enumerate()
+        .flat_map(|(index, row)| {
+            converting::unique_keys(row).into_iter().zip(row).map(
+                move |(key, conversion_result)| {
+                    let name = format!("{prefix}{}_{index}", key.to_ascii_uppercase());
+                    assignment(shell, &name, &conversion_result.converted_text)
+                },
+            )
+        })
+        .collect()
+}
+
+fn assignment(shell: Shell, name: &str, value: &str) -> String {
+    match shell {
+        // Nothing is special inside single quotes, so a quote has to close the string, be
+        // escaped on its own and reopen it
+        Shell::Bash | Shell::Zsh => format!("export {name}='{}'", value.replace('\'', r"'\''")),
+        Shell::Fish => format!(
+            "set -gx {name} '{}'",
+            value.replace('\\', r"\\").replace('\'', r"\'")
+        ),
+        // PowerShell also treats typographic single quotes as quotes, and each is escaped by
+        // doubling it
+        Shell::Powershell => {
+            let mut quoted = String::with_capacity(value.len());
+            for c in value.chars() {
+                if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+                    quoted.push(c);
+                }
+                quoted.push(c);
+            }
+            format!("$env:{name} = '{quoted}'")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OutputFormat;
+
+    fn results() -> Vec<ConversionResult> {
+        vec![
+            ConversionResult {
+                converted_text: String::from("1575149020890"),
+                format: OutputFormat::Millis,
+            },
+            ConversionResult {
+                converted_text: String::from(r"it's \o/"),
+                format: OutputFormat::Strftime(String::from("it's %H")),
+            },
+        ]
+    }
+
+    #[test]
+    fn quotes_for_each_shell() {
+        assert_eq!(
+            output(&results(), Shell::Bash, "TT_"),
+            vec![
+                "export TT_MILLIS='1575149020890'",
+                r"export TT_STRFTIME='it'\''s \o/'"
+            ]
+        );
+        assert_eq!(
+            output(&results(), Shell::Fish, "TT_"),
+            vec![
+                "set -gx TT_MILLIS '1575149020890'",
+                r"set -gx TT_STRFTIME 'it\'s \\o/'"
+            ]
+        );
+        assert_eq!(
+            output(&results(), Shell::Powershell, ""),
+            vec![
+                "$env:MILLIS = '1575149020890'",
+                r"$env:STRFTIME = 'it''s \o/'"
+            ]
+        );
+    }
+
+    #[test]
+    fn suffixes_repeated_keys() {
+        let mut results = results();
+        results.push(ConversionResult {
+            converted_text: String::from("21"),
+            format: OutputFormat::Strftime(String::from("%H")),
+        });
+        assert_eq!(
+            output(&results, Shell::Bash, "TT_"),
+            vec![
+                "export TT_MILLIS='1575149020890'",
+                r"export TT_STRFTIME='it'\''s \o/'",
+                "export TT_STRFTIME_2='21'"
+            ]
+        );
+    }
+
+    #[test]
+    fn suffixes_range_rows() {
+        let row = || results().into_iter().take(1).collect();
+        let rows = vec![row(), row()];
+        assert_eq!(
+            output_rows(&rows, Shell::Zsh, "TT_"),
+            vec![
+                "export TT_MILLIS_0='1575149020890'",
+                "export TT_MILLIS_1='1575149020890'"
+            ]
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use crate::{CalendarUnit, Rounding, Snap, TimeZoneSpec, WeekStart};
+use chrono::prelude::*;
+use chrono::{Days, Months, TimeDelta};
+
+/// Moves `instant` to a boundary of `snap.unit` in `snap.timezone` (UTC when `None`), rounding
+/// down, up, or to the nearer boundary (ties go up). Day and longer units follow the local
+/// calendar, so the start of a day is local midnight even across DST changes. Shorter units use
+/// the offset in effect at `instant`, so a repeated or skipped hour never snaps past it.
+pub fn snap(instant: &DateTime<Utc>, snap: &Snap) -> Option<DateTime<Utc>> {
+    let step = match snap.unit {
+        CalendarUnit::Second => Some(TimeDelta::seconds(1)),
+        CalendarUnit::Minute => Some(TimeDelta::minutes(1)),
+        CalendarUnit::Hour => Some(TimeDelta::hours(1)),
+        _ => None,
+    };
+    let (floor, next) = if let Some(step) = step {
+        let offset = snap.timezone.map_or(Utc.fix(), |tz| tz.offset_at(instant));
+        let local = instant.with_timezone(&offset).naive_local();
+        let floor = floor_naive(local, snap.unit, snap.week_start)?
+            .and_local_timezone(offset)
+            .single()?
+            .to_utc();
+        (floor, floor + step)
+    } else {
+        let local = match snap.timezone {
+            Some(tz) => tz.utc_to_naive(instant),
+            None => instant.naive_utc(),
+        };
+        let floor_local = floor_naive(local, snap.unit, snap.week_start)?;
+        let next_local = match snap.unit {
+            CalendarUnit::Day => floor_local.checked_add_days(Days::new(1))?,
+            CalendarUnit::Week => floor_local.checked_add_days(Days::new(7))?,
+            CalendarUnit::Month => floor_local.checked_add_months(Months::new(1))?,
+            CalendarUnit::Quarter => floor_local.checked_add_months(Months::new(3))?,
+            _ => floor_local.checked_add_months(Months::new(12))?,
+        };
+        (
+            to_utc(floor_local, snap.timezone)?,
+            to_utc(next_local, snap.timezone)?,
+        )
+    };
+    if floor == *instant {
+        return Some(floor);
+    }
+    Some(match snap.rounding {
+        Rounding::Round if *instant - floor < next - *instant => floor,
+        Rounding::Floor => floor,
+        Rounding::Ceil | Rounding::Round => next,
+    })
+}
+
+fn floor_naive(
+    local: NaiveDateTime,
+    unit: CalendarUnit,
+    week_start: WeekStart,
+) -> Option<NaiveDateTime> {
+    let date = local.date();
+    let time = local.time();
+    Some(match unit {
+        CalendarUnit::Second => date.and_hms_opt(time.hour(), time.minute(), time.second())?,
+        CalendarUnit::Minute => date.and_hms_opt(time.hour(), time.minute(), 0)?,
+        CalendarUnit::Hour => date.and_hms_opt(time.hour(), 0, 0)?,
+        CalendarUnit::Day => date.and_hms_opt(0, 0, 0)?,
+        CalendarUnit::Week => {
+            let first_day = match week_start {
+                WeekStart::Monday => Weekday::Mon,
+                WeekStart::Sunday => Weekday::Sun,
+            };
+            let days_into_week = date.weekday().days_since(first_day);
+            date.checked_sub_days(Days::new(days_into_week.into()))?
+                .and_hms_opt(0, 0, 0)?
+        }
+        CalendarUnit::Month => date.with_day(1)?.and_hms_opt(0, 0, 0)?,
+        CalendarUnit::Quarter => {
+            NaiveDate::from_ymd_opt(date.year(), date.month0() / 3 * 3 + 1, 1)?
+                .and_hms_opt(0, 0, 0)?
+        }
+        CalendarUnit::Year => NaiveDate::from_ymd_opt(date.year(), 1, 1)?.and_hms_opt(0, 0, 0)?,
+    })
+}
+
+// Maps a local boundary back to UTC. Boundaries skipped by a DST gap (some zones change at
+// midnight) move forward to the first wall-clock time that exists.
+pub(crate) fn to_utc(
+    local: NaiveDateTime,
+    timezone: Option<TimeZoneSpec>,
+) -> Option<DateTime<Utc>> {
+    let Some(tz) = timezone else {
+        return Some(local.and_utc());
+    };
+    (0..=120)
+        .map(|minutes| local + TimeDelta::minutes(minutes))
+        .find_map(|candidate| tz.naive_to_utc_earliest(candidate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datetime(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().to_utc()
+    }
+
+    fn snap_to(
+        instant: &str,
+        rounding: Rounding,
+        unit: CalendarUnit,
+        timezone: Option<&str>,
+    ) -> Option<DateTime<Utc>> {
+        snap(
+            &datetime(instant),
+            &Snap {
+                rounding,
+                unit,
+                week_start: WeekStart::Monday,
+                timezone: timezone.map(|tz| crate::parse_timezone_spec(tz).unwrap()),
+            },
+        )
+    }
+
+    #[test]
+    fn floor_hour() {
+        assert_eq!(
+            snap_to(
+                "2019-11-30T21:23:40.890Z",
+                Rounding::Floor,
+                CalendarUnit::Hour,
+                None
+            ),
+            Some(datetime("2019-11-30T21:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn ceil_minute() {
+        assert_eq!(
+            snap_to(
+                "2019-11-30T21:23:40.890Z",
+                Rounding::Ceil,
+                CalendarUnit::Minute,
+                None
+            ),
+            Some(datetime("2019-11-30T21:24:00Z"))
+        );
+    }
+
+    #[test]
+    fn ceil_on_boundary_is_unchanged() {
+        assert_eq!(
+            snap_to(
+                "2019-11-30T00:00:00Z",
+                Rounding::Ceil,
+                CalendarUnit::Day,
+                None
+            ),
+            Some(datetime("2019-11-30T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn round_day_to_nearer() {
+        assert_eq!(
+            snap_to(
+                "2019-11-30T11:59:59Z",
+                Rounding::Round,
+                CalendarUnit::Day,
+                None
+            ),
+            Some(datetime("2019-11-30T00:00:00Z"))
+        );
+        assert_eq!(
+            snap_to(
+                "2019-11-30T12:00:00Z",
+                Rounding::Round,
+                CalendarUnit::Day,
+                None
+            ),
+            Some(datetime("2019-12-01T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn floor_day_in_timezone() {
+        assert_eq!(
+            snap_to(
+                "2019-11-30T05:00:00Z",
+                Rounding::Floor,
+                CalendarUnit::Day,
+                Some("America/Los_Angeles")
+            ),
+            Some(datetime("2019-11-29T00:00:00-08:00"))
+        );
+    }
+
+    #[test]
+    fn ceil_day_across_dst_end() {
+        // 2019-11-03 is 25 hours long in Los Angeles
+        assert_eq!(
+            snap_to(
+                "2019-11-03T00:30:00-07:00",
+                Rounding::Ceil,
+                CalendarUnit::Day,
+                Some("America/Los_Angeles")
+            ),
+            Some(datetime("2019-11-04T00:00:00-08:00"))
+        );
+    }
+
+    #[test]
+    fn snap_hour_across_dst_end() {
+        // 01:00-02:00 happens twice in Los Angeles on 2019-11-03; 09:30Z is the second 01:30
+        assert_eq!(
+            snap_to(
+                "2019-11-03T09:30:00Z",
+                Rounding::Ceil,
+                CalendarUnit::Hour,
+                Some("America/Los_Angeles")
+            ),
+            Some(datetime("2019-11-03T10:00:00Z"))
+        );
+        assert_eq!(
+            snap_to(
+                "2019-11-03T09:30:00Z",
+                Rounding::Floor,
+                CalendarUnit::Hour,
+                Some("America/Los_Angeles")
+            ),
+            Some(datetime("2019-11-03T09:00:00Z"))
+        );
+        assert_eq!(
+            snap_to(
+                "2019-11-03T08:30:00Z",
+                Rounding::Floor,
+                CalendarUnit::Hour,
+                Some("America/Los_Angeles")
+            ),
+            Some(datetime("2019-11-03T08:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn floor_hour_in_half_hour_offset() {
+        assert_eq!(
+            snap_to(
+                "2019-11-30T21:23:40Z",
+                Rounding::Floor,
+                CalendarUnit::Hour,
+                Some("Asia/Kolkata")
+            ),
+            Some(datetime("2019-11-30T20:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn floor_day_skipped_midnight() {
+        // Clocks in Sao Paulo jumped from 00:00 to 01:00 on 2018-11-04
+        assert_eq!(
+            snap_to(
+                "2018-11-04T12:00:00-02:00",
+                Rounding::Floor,
+                CalendarUnit::Day,
+                Some("America/Sao_Paulo")
+            ),
+            Some(datetime("2018-11-04T01:00:00-02:00"))
+        );
+    }
+
+    #[test]
+    fn floor_week_monday_and_sunday() {
+        // 2019-11-30 is a Saturday
+        assert_eq!(
+            snap_to(
+                "2019-11-30T21:23:40Z",
+                Rounding::Floor,
+                CalendarUnit::Week,
+                None
+            ),
+            Some(datetime("2019-11-25T00:00:00Z"))
+        );
+        assert_eq!(
+            snap(
+                &datetime("2019-11-30T21:23:40Z"),
+                &Snap {
+                    rounding: Rounding::Floor,
+                    unit: CalendarUnit::Week,
+                    week_start: WeekStart::Sunday,
+                    timezone: None,
+                },
+            ),
+            Some(datetime("2019-11-24T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn floor_and_ceil_quarter() {
+        assert_eq!(
+            snap_to(
+                "2019-11-30T21:23:40Z",
+                Rounding::Floor,
+                CalendarUnit::Quarter,
+                None
+            ),
+            Some(datetime("2019-10-01T00:00:00Z"))
+        );
+        assert_eq!(
+            snap_to(
+                "2019-11-30T21:23:40Z",
+                Rounding::Ceil,
+                CalendarUnit::Quarter,
+                None
+            ),
+            Some(datetime("2020-01-01T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn floor_month_and_year() {
+        assert_eq!(
+            snap_to(
+                "2019-11-30T21:23:40Z",
+                Rounding::Floor,
+                CalendarUnit::Month,
+                None
+            ),
+            Some(datetime("2019-11-01T00:00:00Z"))
+        );
+        assert_eq!(
+            snap_to(
+                "2019-11-30T21:23:40Z",
+                Rounding::Round,
+                CalendarUnit::Year,
+                None
+            ),
+            Some(datetime("2020-01-01T00:00:00Z"))
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use super::converting::ConversionResult;
+use super::{Template, TemplatePart};
+
+/// Fills in the template's placeholders from the conversions of `template.outputs()`, the input
+/// as written and the name of the output timezone.
+pub fn render(
+    template: &Template,
+    conversion_results: &[ConversionResult],
+    input: &str,
+    zone: &str,
+) -> String {
+    let mut rendered = String::new();
+    for part in &template.parts {
+        match part {
+            TemplatePart::Text(text) => rendered.push_str(text),
+            TemplatePart::Output(format) => {
+                if let Some(conversion_result) = conversion_results
+                    .iter()
+                    .find(|conversion_result| conversion_result.format == *format)
+                {
+                    rendered.push_str(&conversion_result.converted_text);
+                }
+            }
+            TemplatePart::Input => rendered.push_str(input),
+            TemplatePart::Zone => rendered.push_str(zone),
+        }
+    }
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converting;
+    use crate::{ConvertOptions, OutputFormat, parse_template};
+    use chrono::TimeDelta;
+    use chrono::prelude::*;
+
+    fn result(format: OutputFormat, converted_text: &str) -> ConversionResult {
+        ConversionResult {
+            converted_text: String::from(converted_text),
+            format,
+        }
+    }
+
+    #[test]
+    fn fills_in_placeholders() {
+        let template =
+            parse_template(r"*{{ utc }}* ({millis}, {epoch_seconds_3}) \{{input}\} in {zone}")
+                .unwrap();
+        assert_eq!(
+            template.outputs(),
+            vec![
+                OutputFormat::Utc,
+                OutputFormat::Millis,
+                OutputFormat::Epoch(crate::EpochUnit::Seconds, 3)
+            ]
+        );
+        let results = [
+            result(OutputFormat::Utc, "2019-11-30T21:23:40.890Z"),
+            result(OutputFormat::Millis, "1575149020890"),
+            result(
+                OutputFormat::Epoch(crate::EpochUnit::Seconds, 3),
+                "1575149020.890",
+            ),
+        ];
+        assert_eq!(
+            render(&template, &results, "1575149020890", "UTC"),
+            "*2019-11-30T21:23:40.890Z* (1575149020890, 1575149020.890) {1575149020890} in UTC"
+        );
+    }
+
+    #[test]
+    fn renders_duration_since_units() {
+        let template = parse_template("took {duration_since_seconds}").unwrap();
+        let instant = DateTime::parse_from_rfc3339("2019-11-30T13:27:45Z")
+            .unwrap()
+            .to_utc();
+        let now = instant + TimeDelta::seconds(90);
+        let results = converting::convert(
+            &instant,
+            &now,
+            &template.outputs(),
+            &ConvertOptions::default(),
+        );
+        assert_eq!(render(&template, &results, "", "UTC"), "took 90.0 s ago");
+    }
+}
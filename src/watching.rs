@@ -0,0 +1,64 @@
+use chrono::prelude::*;
+use std::io::{self, ErrorKind, IsTerminal, Write};
+use std::thread;
+use std::time::Duration;
+
+/// Calls `render` with the current time every `interval` and prints the lines it returns, until
+/// the process is interrupted or stdout is closed. On a terminal each refresh replaces the
+/// previous one; when piped, every refresh is a single tab-separated line.
+pub fn watch(
+    interval: Duration,
+    mut render: impl FnMut(&DateTime<Utc>) -> Result<Vec<String>, &'static str>,
+) -> Result<(), &'static str> {
+    let terminal = io::stdout().is_terminal();
+    let mut drawn = 0;
+    loop {
+        let lines = render(&Utc::now())?;
+        let frame = if terminal {
+            redraw(drawn, &lines)
+        } else {
+            format!("{}\n", lines.join("\t"))
+        };
+        drawn = lines.len();
+        let mut stdout = io::stdout().lock();
+        match stdout
+            .write_all(frame.as_bytes())
+            .and_then(|()| stdout.flush())
+        {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::BrokenPipe => return Ok(()),
+            Err(_) => return Err("Could not write to stdout"),
+        }
+        drop(stdout);
+        thread::sleep(interval);
+    }
+}
+
+// Moves the cursor back up over the `previous` lines already drawn and overwrites them with
+// `lines`, clearing anything left below.
+fn redraw(previous: usize, lines: &[String]) -> String {
+    let mut frame = if previous > 0 {
+        format!("\x1b[{previous}A")
+    } else {
+        String::new()
+    };
+    for line in lines {
+        frame.push_str("\r\x1b[2K");
+        frame.push_str(line);
+        frame.push('\n');
+    }
+    frame.push_str("\x1b[J");
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redraw_overwrites_previous_lines() {
+        let lines = vec![String::from("a"), String::from("b")];
+        assert_eq!(redraw(0, &lines), "\r\x1b[2Ka\n\r\x1b[2Kb\n\x1b[J");
+        assert_eq!(redraw(2, &lines), "\x1b[2A\r\x1b[2Ka\n\r\x1b[2Kb\n\x1b[J");
+    }
+}